@@ -1,11 +1,13 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     env,
-    fs::File,
-    io::{BufReader, Read},
-    path::{Path, PathBuf}
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
 };
-use bindgen::{Bindings, BindgenError};
+use bindgen::BindgenError;
 
 // SUNDIALS has a few non-negative constants that need to be parsed as an i32.
 // This is an attempt at doing so generally.
@@ -16,7 +18,7 @@ impl bindgen::callbacks::ParseCallbacks for ParseSignedConstants {
     fn int_macro(&self, name: &str, _value: i64) -> Option<bindgen::callbacks::IntKind> {
         let prefix: String = name.chars().take_while(|c| *c != '_').collect();
         match prefix.as_ref() {
-            "CV" | "IDA" | "KIN" | "SUN" => Some(bindgen::callbacks::IntKind::Int),
+            "ARK" | "CV" | "IDA" | "KIN" | "SUN" | "SUNKLU" => Some(bindgen::callbacks::IntKind::Int),
             _ => None,
         }
     }
@@ -73,8 +75,724 @@ struct Library {
     lib: Option<String>,
 }
 
-/// Build the Sundials code vendor with sundials-sys.
-fn build_vendor_sundials(klu: &Library) -> (Library, &'static str) {
+impl std::fmt::Display for Library {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "include: {}, lib: {}",
+               self.inc.as_deref().unwrap_or("-"),
+               self.lib.as_deref().unwrap_or("-"))
+    }
+}
+
+/// Environment variables read by the build script (besides those
+/// starting with `SUNDIALS_`), listed by `SUNDIALS_SYS_VERBOSE`.
+const READ_VARS: &[&str] = &[
+    "TARGET", "HOST", "PROFILE", "OPT_LEVEL", "NUM_JOBS", "CC", "CFLAGS",
+    "CMAKE_TOOLCHAIN_FILE", "CMAKE_GENERATOR", "BINDGEN_EXTRA_CLANG_ARGS",
+    "LIBCLANG_PATH", "DEP_SUITESPARSE_SUITESPARSE_INCLUDE",
+    "DEP_SUITESPARSE_SUITESPARSE_LIB", "KLU_INCLUDE_DIR", "KLU_LIBRARY_DIR",
+    "SUITESPARSE_SOURCE_DIR", "CONDA_PREFIX", "PKG_CONFIG_PATH",
+    "NIX_CFLAGS_COMPILE", "NIX_LDFLAGS", "VCPKG_ROOT", "VCPKGRS_TRIPLET",
+    "BLA_VENDOR", "LAPACK_LIBRARIES", "MPICC", "MPI_HOME", "CUDA_PATH",
+    "PETSC_DIR", "PETSC_ARCH", "CALIPER_DIR", "ANDROID_NDK_HOME",
+    "WASI_SDK_PREFIX", "EMSDK", "CARGO_NET_OFFLINE", "SOURCE_DATE_EPOCH",
+];
+
+/// Whether `SUNDIALS_SYS_VERBOSE` is set (but not to 0): the build
+/// script then describes each of its decisions.
+fn verbose_enabled() -> bool {
+    env::var("SUNDIALS_SYS_VERBOSE").is_ok_and(|v| v != "0")
+}
+
+/// One line of the `SUNDIALS_SYS_VERBOSE` summary: the `step` of the
+/// build and what was found or decided.
+fn verbose(step: &str, detail: impl std::fmt::Display) {
+    if verbose_enabled() {
+        println!("cargo:warning=[sundials-sys] {:<10} {}", step, detail);
+    }
+}
+
+/// Locations of the third party libraries used by the optional
+/// linear solvers.
+struct Deps {
+    klu: Library,
+    superlumt: Library,
+    superludist: Library,
+    magma: Library,
+    onemkl: Library,
+    petsc: Library,
+    hypre: Library,
+    trilinos: Library,
+    xbraid: Library,
+    caliper: Library,
+    /// Other include directories, e.g. the one of the PETSc
+    /// configuration header `petscconf.h`.
+    extra_inc: Vec<String>,
+}
+
+impl Deps {
+    /// Include directories for bindgen: the SUNDIALS one followed by
+    /// those of the dependencies.
+    fn include_dirs(&self, sundials: &Library) -> Vec<Option<String>> {
+        [sundials, &self.klu, &self.superlumt, &self.superludist,
+         &self.magma, &self.onemkl, &self.petsc, &self.hypre,
+         &self.trilinos, &self.xbraid, &self.caliper].iter()
+            .map(|l| l.inc.clone())
+            .chain(self.extra_inc.iter().map(|d| Some(d.clone())))
+            .collect()
+    }
+}
+
+/// Lock on a directory, held as long as the value lives.  It is
+/// implemented with the (atomic) creation of a sibling directory so
+/// as to be portable.
+struct DirLock(Option<PathBuf>);
+
+impl DirLock {
+    /// Locks older than that are considered stale (the build holding
+    /// them must have been killed).
+    const STALE: Duration = Duration::from_secs(3600);
+
+    fn acquire(dir: &Path) -> Self {
+        let lock = dir.with_extension("lock");
+        if let Some(parent) = lock.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        loop {
+            match fs::create_dir(&lock) {
+                Ok(()) => return DirLock(Some(lock)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&lock)
+                        .and_then(|m| m.modified()).ok()
+                        .and_then(|t| t.elapsed().ok())
+                        .is_some_and(|d| d > Self::STALE);
+                    if stale {
+                        let _ = fs::remove_dir(&lock);
+                    } else {
+                        thread::sleep(Duration::from_millis(500));
+                    }
+                }
+                Err(_) => return DirLock(None),
+            }
+        }
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        if let Some(lock) = &self.0 {
+            let _ = fs::remove_dir(lock);
+        }
+    }
+}
+
+/// Directory `name/<hash of key>` shared by all builds of this crate
+/// in the same target directory (and thus surviving changes of the
+/// enabled features, contrarily to OUT_DIR) or, if
+/// `SUNDIALS_SYS_CACHE_DIR` is set, by all the builds using this
+/// cache (across workspaces and `cargo clean`).
+fn shared_dir(name: &str, key: &str) -> Option<PathBuf> {
+    let mut parts = vec![key];
+    let base = match env::var_os("SUNDIALS_SYS_CACHE_DIR") {
+        Some(dir) => {
+            // The CMake trees record the location of the sources.
+            parts.push(env!("CARGO_MANIFEST_DIR"));
+            PathBuf::from(dir)
+        }
+        None => {
+            // OUT_DIR = <target>/<profile>/build/sundials-sys-<hash>/out
+            let out_dir = PathBuf::from(env::var("OUT_DIR").ok()?);
+            out_dir.ancestors().nth(2)?.join("sundials-sys-shared")
+        }
+    };
+    Some(base.join(name).join(&sha256_hex(&parts)[..16]))
+}
+
+/// Hexadecimal SHA-256 of the `parts`.  Unlike `DefaultHasher`, it does
+/// not change with the Rust release, so the keys of the shared
+/// directories stay valid when the toolchain is updated.
+fn sha256_hex<T: AsRef<[u8]>>(parts: &[T]) -> String {
+    use sha2::{Digest, Sha256};
+    let mut h = Sha256::new();
+    for p in parts {
+        // Length prefixed, so that the concatenation is unambiguous.
+        h.update((p.as_ref().len() as u64).to_le_bytes());
+        h.update(p);
+    }
+    h.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Look for a SUNDIALS install (with its headers) in `prefixes`.
+fn probe_prefixes(prefixes: &[&str]) -> Option<Library> {
+    prefixes.iter().map(Path::new)
+        .find(|p| p.join("include/sundials/sundials_config.h").is_file())
+        .map(|p| Library {
+            inc: Some(p.join("include").display().to_string()),
+            lib: Some(p.join("lib").display().to_string()),
+        })
+}
+
+/// Prefixes where the Homebrew `formula` or MacPorts may install a
+/// library on macOS.  `brew --prefix` gives the one of the current
+/// Homebrew (`/opt/homebrew` on Apple Silicon, `/usr/local` on Intel).
+fn macos_prefixes(formula: &str) -> Vec<String> {
+    let mut prefixes = vec![];
+    if let Ok(out) = std::process::Command::new("brew")
+        .args(["--prefix", formula]).output()
+    {
+        if out.status.success() {
+            prefixes.push(String::from_utf8_lossy(&out.stdout).trim().to_string());
+        }
+    }
+    for brew in ["/opt/homebrew", "/usr/local"] {
+        prefixes.push(format!("{}/opt/{}", brew, formula));
+    }
+    prefixes.push("/opt/local".to_string()); // MacPorts
+    prefixes
+}
+
+/// Prefixes of the active conda environment (`CONDA_PREFIX`).  On
+/// Windows, conda-forge packages install C libraries in `Library`.
+fn conda_prefixes() -> Vec<String> {
+    let Ok(prefix) = env::var("CONDA_PREFIX") else { return vec![] };
+    let windows = env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "windows");
+    if windows {
+        vec![format!("{}\\Library", prefix), prefix]
+    } else {
+        vec![prefix]
+    }
+}
+
+/// Look for SuiteSparse (KLU) in `prefixes`.
+fn probe_klu(prefixes: &[String]) -> Option<Library> {
+    prefixes.iter().map(Path::new)
+        .flat_map(|p| [(p.join("include/suitesparse"), p.join("lib")),
+                       (p.join("include"), p.join("lib"))])
+        .find(|(inc, _)| inc.join("klu.h").is_file())
+        .map(|(inc, lib)| Library {
+            inc: Some(inc.display().to_string()),
+            lib: Some(lib.display().to_string()),
+        })
+}
+
+/// Arguments of the options `opts` (e.g. "-I") in the flags of the
+/// environment variable `var`, whether separated from them or not.
+fn flag_args(var: &str, opts: &[&str]) -> Vec<String> {
+    split_flag_args(&env::var(var).unwrap_or_default(), opts)
+}
+
+/// Arguments of the options `opts` in `flags`.
+fn split_flag_args(flags: &str, opts: &[&str]) -> Vec<String> {
+    let mut flags = flags.split_whitespace();
+    let mut args = vec![];
+    while let Some(f) = flags.next() {
+        for opt in opts {
+            if f == *opt {
+                args.extend(flags.next().map(|a| a.to_string()));
+            } else if let Some(a) = f.strip_prefix(opt) {
+                args.push(a.to_string());
+            }
+        }
+    }
+    args
+}
+
+/// Include directories set up by Nix for its C compiler wrapper.
+/// libclang (used by bindgen) does not know about them.
+fn nix_include_dirs() -> Vec<String> {
+    flag_args("NIX_CFLAGS_COMPILE", &["-isystem", "-I"])
+}
+
+/// Look for SUNDIALS in the Nix environment (nix-shell, derivations).
+fn nix_sundials() -> Option<Library> {
+    let inc = nix_include_dirs().into_iter()
+        .find(|d| Path::new(d).join("sundials/sundials_config.h").is_file())?;
+    let lib = flag_args("NIX_LDFLAGS", &["-L"]).into_iter().find(|d| {
+        fs::read_dir(d).is_ok_and(|mut entries| entries.any(|e| {
+            e.is_ok_and(|e| e.file_name().to_string_lossy()
+                        .starts_with("libsundials_"))
+        }))
+    });
+    Some(Library { inc: Some(inc), lib })
+}
+
+/// Whether the SUNDIALS whose headers are in `inc_dirs` (or in the
+/// default system locations) was built with MPI.
+fn mpi_enabled(inc_dirs: &[Option<String>]) -> bool {
+    let system = ["/usr/include", "/usr/local/include"];
+    inc_dirs.iter().flatten().map(|d| d.as_str()).chain(system)
+        .map(|d| Path::new(d).join("sundials/sundials_config.h"))
+        .find(|h| h.is_file())
+        .and_then(|h| fs::read_to_string(h).ok())
+        .is_some_and(|config_h| config_h.lines().any(|l| {
+            let w: Vec<_> = l.split_whitespace().collect();
+            w.len() >= 2 && w[0] == "#define" && w[1] == "SUNDIALS_MPI_ENABLED"
+                && w.get(2) != Some(&"0")
+        }))
+}
+
+/// Functions added in recent versions (or only present in some
+/// builds) whose availability is exposed as the cfg
+/// `sundials_has_fn_<name>`.
+const OPTIONAL_FUNCTIONS: &[&str] = &[
+    "SUNProfiler_Create",            // 6.0
+    "SUNLogger_Create",              // 6.2
+    "CVodePrintAllStats",            // 6.2
+    "IDAPrintAllStats",              // 6.2
+    "KINPrintAllStats",              // 6.2
+    "CVodeSetDeltaGammaMaxLSetup",   // 6.2
+    "SUNAdaptController_Soderlind",  // 6.7
+    "ARKStepSetAdaptController",     // 6.7
+    "SUNContext_PushErrHandler",     // 7.0
+    "SUNContext_GetLastError",       // 7.0
+    "ARKodeEvolve",                  // 7.1
+    "ARKodeSetOrder",                // 7.1
+    "SUNStepper_Create",             // 7.2
+    "CVodeSetMonitorFn",             // monitoring builds
+];
+
+/// The [`OPTIONAL_FUNCTIONS`] declared in the bindings `bindings_rs`
+/// and, if `nm` can list the symbols of the SUNDIALS libraries in
+/// `lib_dir`, defined in one of them.
+fn available_functions(
+    bindings_rs: &Path, lib_dir: Option<&str>
+) -> Vec<&'static str> {
+    let bindings = fs::read_to_string(bindings_rs).unwrap_or_default();
+    let mut fns: Vec<_> = OPTIONAL_FUNCTIONS.iter().copied()
+        .filter(|f| bindings.contains(&format!("fn {}(", f)))
+        .collect();
+    let Some(entries) = lib_dir.and_then(|d| fs::read_dir(d).ok()) else {
+        return fns
+    };
+    let mut symbols = HashSet::new();
+    let mut listed = false;
+    for path in entries.flatten().map(|e| e.path()) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !name.starts_with("libsundials_") {
+            continue
+        }
+        let mut nm = std::process::Command::new(
+            env::var("NM").unwrap_or("nm".into()));
+        nm.arg("-g");
+        if name.contains(".so") {
+            nm.arg("-D");
+        }
+        let Ok(out) = nm.arg(&path).output() else { continue };
+        if !out.status.success() {
+            continue
+        }
+        listed = true;
+        for line in String::from_utf8_lossy(&out.stdout).lines() {
+            let w: Vec<_> = line.split_whitespace().collect();
+            if let [.., kind, sym] = w[..] {
+                if kind != "U" {
+                    // Mach-O symbols have a leading underscore.
+                    symbols.insert(sym.trim_start_matches('_').to_string());
+                }
+            }
+        }
+    }
+    if listed {
+        fns.retain(|f| symbols.contains(*f));
+    }
+    fns
+}
+
+/// Whether a solver (ARKODE, CVODE(S), IDA(S) or KINSOL) is enabled.
+/// If not, only the core of SUNDIALS and the serial vector are bound
+/// and linked (not the matrices, linear and nonlinear solvers).
+fn solvers_enabled() -> bool {
+    cfg!(any(feature = "arkode", feature = "cvode", feature = "cvodes",
+             feature = "ida", feature = "idas", feature = "kinsol"))
+}
+
+/// The MPI compiler wrapper: `MPICC` if set, otherwise `mpicc` in
+/// `MPI_HOME/bin` or the `PATH`.
+fn mpicc() -> String {
+    env::var("MPICC").ok()
+        .or_else(|| env::var("MPI_HOME").ok().map(|home| {
+            Path::new(&home).join("bin").join("mpicc").display().to_string()
+        }))
+        .unwrap_or("mpicc".into())
+}
+
+/// Compile and link flags of MPI as given by its compiler wrapper
+/// (see [`mpicc`]).  Open MPI understands `--showme`, MPICH and its
+/// derivatives `-show`.
+fn mpi_flags() -> String {
+    let mpicc = mpicc();
+    ["--showme", "-show"].iter().find_map(|opt| {
+        let out = std::process::Command::new(&mpicc).arg(opt).output().ok()?;
+        if out.status.success() { String::from_utf8(out.stdout).ok() }
+        else { None }
+    }).unwrap_or_default()
+}
+
+/// Compile, in a static library, accessors to the MPI communicators
+/// (`MPI_COMM_NULL` and `MPI_COMM_WORLD` are not constants bindgen
+/// can translate) and link it with MPI.
+fn build_mpi_comm(sundials: &Library, static_define: bool) {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let comm_c = out_dir.join("sundials_sys_comm.c");
+    fs::write(&comm_c, "\
+#include <sundials/sundials_types.h>
+#include <mpi.h>
+#if SUNDIALS_VERSION_MAJOR >= 7
+SUNComm sundials_sys_comm_null(void) { return SUN_COMM_NULL; }
+SUNComm sundials_sys_comm_world(void) { return MPI_COMM_WORLD; }
+#else
+/* SUNContext_Create takes a pointer to the communicator. */
+static MPI_Comm sundials_sys_world;
+void *sundials_sys_comm_world(void) {
+  sundials_sys_world = MPI_COMM_WORLD;
+  return &sundials_sys_world;
+}
+#endif
+").expect("Couldn't write sundials_sys_comm.c!");
+    let flags = mpi_flags();
+    let mut build = cc::Build::new();
+    if static_define {
+        build.define("SUNDIALS_STATIC_DEFINE", None);
+    }
+    build.file(&comm_c)
+        .includes(&sundials.inc)
+        .includes(split_flag_args(&flags, &["-I"]))
+        .compile("sundials_sys_comm");
+    for dir in split_flag_args(&flags, &["-L"]) {
+        println!("cargo:rustc-link-search=native={}", dir);
+    }
+    let libs = split_flag_args(&flags, &["-l"]);
+    if libs.is_empty() {
+        println!("cargo:rustc-link-lib=mpi");
+    }
+    for lib in libs {
+        println!("cargo:rustc-link-lib={}", lib);
+    }
+}
+
+/// Look for SUNDIALS with pkg-config (or pkgconf).  Only the paths
+/// are used, the libraries to link are determined by the features
+/// (their names, `sundials_<module>`, are those of the `.pc` files).
+/// Among the reported paths, the ones containing the SUNDIALS headers
+/// and the reported library are preferred.
+#[cfg(feature = "pkg-config")]
+fn pkg_config_sundials() -> Option<Library> {
+    let lib = ["sundials-core", "sundials-nvecserial", "sundials-cvode"].iter()
+        .find_map(|name| {
+            pkg_config::Config::new().cargo_metadata(false).probe(name).ok()
+        })?;
+    let inc = lib.include_paths.iter()
+        .find(|p| p.join("sundials").join("sundials_config.h").is_file())
+        .or(lib.include_paths.first());
+    let has_lib = |dir: &Path| lib.libs.iter().any(|name| {
+        ["lib{}.so", "lib{}.dylib", "lib{}.a", "{}.lib", "lib{}.dll.a"].iter()
+            .any(|f| dir.join(f.replace("{}", name)).is_file())
+    });
+    let dir = lib.link_paths.iter().find(|p| has_lib(p))
+        .or(lib.link_paths.first());
+    Some(Library {
+        inc: inc.map(|p| p.display().to_string()),
+        lib: dir.map(|p| p.display().to_string()),
+    })
+}
+
+#[cfg(not(feature = "pkg-config"))]
+fn pkg_config_sundials() -> Option<Library> { None }
+
+/// Location of a dependency, given by `<VAR>_INCLUDE_DIR` and
+/// `<VAR>_LIBRARY_DIR` or, failing that and if `enabled`, by the
+/// pkg-config package `pkg`.
+#[allow(unused_variables)]
+fn dependency_library(var: &str, pkg: &str, enabled: bool) -> Library {
+    let inc = env::var(format!("{}_INCLUDE_DIR", var)).ok();
+    let lib = env::var(format!("{}_LIBRARY_DIR", var)).ok();
+    #[cfg(feature = "pkg-config")]
+    if enabled && inc.is_none() {
+        if let Ok(found) = pkg_config::Config::new().cargo_metadata(false)
+            .probe(pkg)
+        {
+            return Library {
+                inc: found.include_paths.first()
+                    .map(|p| p.display().to_string()),
+                lib: lib.or_else(|| {
+                    found.link_paths.first().map(|p| p.display().to_string())
+                }),
+            }
+        }
+    }
+    Library { inc, lib }
+}
+
+/// Location of SuperLU_MT (`SUPERLUMT_INCLUDE_DIR`,...).
+fn superlumt_library() -> Library {
+    dependency_library("SUPERLUMT", "superlu_mt", cfg!(feature = "superlumt"))
+}
+
+/// Location of SuperLU_DIST (`SUPERLUDIST_INCLUDE_DIR`,...).
+fn superludist_library() -> Library {
+    dependency_library("SUPERLUDIST", "superlu_dist",
+                       cfg!(feature = "superludist"))
+}
+
+/// Location of Trilinos, installed in `Trilinos_DIR`.
+fn trilinos_library() -> Library {
+    let dir = env::var("Trilinos_DIR").ok().filter(|_| cfg!(feature = "trilinos"));
+    Library {
+        inc: dir.as_ref().map(|d| format!("{}/include", d)),
+        lib: dir.as_ref().map(|d| format!("{}/lib", d)),
+    }
+}
+
+/// Location of XBraid, installed in `XBRAID_DIR`.
+fn xbraid_library() -> Library {
+    let dir = env::var("XBRAID_DIR").ok().filter(|_| cfg!(feature = "xbraid"));
+    Library {
+        inc: dir.as_ref().map(|d| format!("{}/include", d)),
+        lib: dir.as_ref().map(|d| format!("{}/lib", d)),
+    }
+}
+
+/// Location of Caliper, installed in `CALIPER_DIR`.  Its header
+/// `caliper/cali.h` is included by the SUNDIALS profiler.
+fn caliper_library() -> Library {
+    let dir = env::var("CALIPER_DIR").ok().filter(|_| cfg!(feature = "caliper"));
+    Library {
+        inc: dir.as_ref().map(|d| format!("{}/include", d)),
+        lib: dir.as_ref().map(|d| format!("{}/lib", d)),
+    }
+}
+
+/// Location of hypre (`HYPRE_INCLUDE_DIR`,...).
+fn hypre_library() -> Library {
+    dependency_library("HYPRE", "HYPRE", cfg!(feature = "hypre"))
+}
+
+/// Location of PETSc and the include directory of its configuration:
+/// `PETSC_DIR` and, for a build in the source tree,
+/// `$PETSC_DIR/$PETSC_ARCH` or, failing that, pkg-config.
+fn petsc_library() -> (Library, Vec<String>) {
+    if !cfg!(feature = "petsc") {
+        return (Library { inc: None, lib: None }, vec![])
+    }
+    if let Ok(dir) = env::var("PETSC_DIR") {
+        let arch = match env::var("PETSC_ARCH") {
+            Ok(arch) => format!("{}/{}", dir, arch),
+            Err(_) => dir.clone(),
+        };
+        let petsc = Library {
+            inc: Some(format!("{}/include", dir)),
+            lib: Some(format!("{}/lib", arch)),
+        };
+        return (petsc, vec![format!("{}/include", arch)])
+    }
+    #[cfg(feature = "pkg-config")]
+    if let Ok(found) = pkg_config::Config::new().cargo_metadata(false)
+        .probe("PETSc")
+    {
+        let mut inc: Vec<_> = found.include_paths.iter()
+            .map(|p| p.display().to_string()).collect();
+        let petsc = Library {
+            inc: (!inc.is_empty()).then(|| inc.remove(0)),
+            lib: found.link_paths.first().map(|p| p.display().to_string()),
+        };
+        return (petsc, inc)
+    }
+    (Library { inc: None, lib: None }, vec![])
+}
+
+/// Link directives for SuperLU_DIST and its dependencies (ParMETIS,
+/// METIS, BLAS, found in `PARMETIS_LIBRARY_DIR` if set) needed by the
+/// static SUNDIALS SuperLU_DIST linear solver.
+fn superludist_directives() -> Vec<String> {
+    let mut directives = vec![];
+    let dirs = [superludist_library().lib,
+                env::var("PARMETIS_LIBRARY_DIR").ok()];
+    for dir in dirs.iter().flatten() {
+        directives.push(format!("rustc-link-search=native={}", dir));
+    }
+    for lib in ["superlu_dist", "parmetis", "metis", "blas"] {
+        directives.push(format!("rustc-link-lib={}", lib));
+    }
+    directives
+}
+
+/// Threading of SuperLU_MT ("PTHREAD" or "OPENMP"), given by
+/// `SUPERLUMT_THREAD_TYPE`.
+fn superlumt_thread_type() -> String {
+    env::var("SUPERLUMT_THREAD_TYPE")
+        .map_or("PTHREAD".into(), |t| t.to_uppercase())
+}
+
+/// SuiteSparse libraries needed by KLU, in link order.
+const KLU_LIBRARIES: [&str; 5] =
+    ["klu", "amd", "btf", "colamd", "suitesparseconfig"];
+
+/// Link directives for the static KLU libraries (feature
+/// `static_all`).  They are taken from the KLU library directory or,
+/// if it has no static libraries, built from the SuiteSparse sources
+/// in `SUITESPARSE_SOURCE_DIR`.
+fn klu_static_directives() -> Vec<String> {
+    let archive = |dir: &Path, name: &str| {
+        [format!("lib{}.a", name), format!("{}_static.lib", name),
+         format!("{}.lib", name)].into_iter()
+            .find(|f| dir.join(f).is_file())
+            .map(|f| if f.ends_with("_static.lib") {
+                format!("{}_static", name)
+            } else {
+                name.to_string()
+            })
+    };
+    let lib = env::var("DEP_SUITESPARSE_SUITESPARSE_LIB")
+        .or_else(|_| env::var("KLU_LIBRARY_DIR")).ok();
+    let mut dir = lib.map(PathBuf::from);
+    if dir.as_deref().and_then(|d| archive(d, "klu")).is_none() {
+        let Ok(src) = env::var("SUITESPARSE_SOURCE_DIR") else {
+            panic!("static_all: no static KLU libraries found, set \
+                    SUITESPARSE_SOURCE_DIR to build them from the \
+                    SuiteSparse sources.")
+        };
+        let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+        let dst = cmake::Config::new(src)
+            .define("SUITESPARSE_ENABLE_PROJECTS",
+                    "suitesparse_config;amd;btf;colamd;klu")
+            .define("BUILD_STATIC_LIBS", "ON")
+            .define("BUILD_SHARED_LIBS", "OFF")
+            .define("KLU_USE_CHOLMOD", "OFF")
+            .define("SUITESPARSE_USE_CUDA", "OFF")
+            .define("SUITESPARSE_USE_FORTRAN", "OFF")
+            .define("CMAKE_INSTALL_LIBDIR", "lib")
+            .out_dir(out_dir.join("suitesparse"))
+            .build();
+        dir = Some(dst.join("lib"));
+    }
+    let dir = dir.unwrap();
+    let mut directives = vec![
+        format!("rustc-link-search=native={}", dir.display())];
+    for name in KLU_LIBRARIES {
+        match archive(&dir, name) {
+            Some(n) => directives.push(format!("rustc-link-lib=static={}", n)),
+            None => panic!("static_all: no static library {} in {}",
+                           name, dir.display()),
+        }
+    }
+    directives
+}
+
+/// Link directives for SuperLU_MT (and the BLAS it uses) needed by
+/// the static SUNDIALS SuperLU_MT linear solver.
+fn superlumt_directives() -> Vec<String> {
+    let mut directives = vec![];
+    if let Some(dir) = superlumt_library().lib {
+        directives.push(format!("rustc-link-search=native={}", dir));
+    }
+    let thread_type = superlumt_thread_type();
+    directives.push(format!("rustc-link-lib=superlu_mt_{}", thread_type));
+    directives.push("rustc-link-lib=blas".into());
+    if thread_type == "OPENMP" {
+        directives.push("rustc-link-lib=gomp".into());
+    }
+    directives
+}
+
+/// Extra arguments for the CMake configuration of the vendored
+/// SUNDIALS, from `SUNDIALS_SYS_CMAKE_ARGS`: `-D` flags separated by
+/// spaces or semicolons (a semicolon not followed by `-D` is part of
+/// the value, e.g. `-DSUNDIALS_GINKGO_BACKENDS=REF;OMP`).
+fn cmake_args() -> Vec<String> {
+    let args = env::var("SUNDIALS_SYS_CMAKE_ARGS").unwrap_or_default();
+    let mut flags = vec![];
+    for arg in args.split_whitespace() {
+        for (i, a) in arg.split(";-D").enumerate() {
+            if i == 0 {
+                flags.push(a.to_string())
+            } else {
+                flags.push(format!("-D{}", a))
+            }
+        }
+    }
+    flags
+}
+
+/// Environment variables (with their values) setting the C and C++
+/// compilers and flags for the target, with the names understood by
+/// the `cc` crate (e.g. `CC_x86_64-unknown-linux-gnu`, `TARGET_CC`).
+fn compiler_env() -> Vec<(String, String)> {
+    let target = env::var("TARGET").unwrap_or_default();
+    let mut vars = vec![];
+    for tool in ["CC", "CFLAGS", "CXX", "CXXFLAGS"] {
+        for name in [format!("{}_{}", tool, target),
+                     format!("{}_{}", tool, target.replace('-', "_")),
+                     format!("TARGET_{}", tool), tool.to_string()] {
+            if let Ok(value) = env::var(&name) {
+                vars.push((name, value));
+            }
+        }
+    }
+    vars
+}
+
+/// Sanitizers with which the C code must be instrumented.  They are
+/// taken from `SUNDIALS_SYS_SANITIZE` (comma separated) if set, and
+/// otherwise follow the ones enabled for Rust (`-Zsanitizer=...`).
+fn sanitizers() -> Vec<String> {
+    let s = env::var("SUNDIALS_SYS_SANITIZE")
+        .or_else(|_| env::var("CARGO_CFG_SANITIZE"))
+        .unwrap_or_default();
+    s.split(',').map(|s| s.trim()).filter(|s| !s.is_empty())
+        .map(|s| s.to_string()).collect()
+}
+
+/// Version of the SUNDIALS sources in `vendor` (a git submodule).
+const VENDOR_VERSION: &str = "7.1.1";
+
+/// SUNDIALS version to build when vendoring: the one of the sources
+/// given by `SUNDIALS_SYS_VENDOR_SOURCE` (read from their
+/// `CMakeLists.txt`) or [`VENDOR_VERSION`].
+fn vendor_version() -> String {
+    let Some(dir) = env::var_os("SUNDIALS_SYS_VENDOR_SOURCE") else {
+        return VENDOR_VERSION.to_string()
+    };
+    let cmake = fs::read_to_string(Path::new(&dir).join("CMakeLists.txt"))
+        .unwrap_or_default();
+    // set(PACKAGE_VERSION_MAJOR "6")
+    let part = |name: &str| cmake.lines().find_map(|l| {
+        let v = l.trim().strip_prefix("set(PACKAGE_VERSION_")?
+            .strip_prefix(name)?.trim_end_matches(')').trim();
+        Some(v.trim_matches('"').to_string())
+    });
+    match (part("MAJOR "), part("MINOR "), part("PATCH ")) {
+        (Some(major), Some(minor), Some(patch)) =>
+            format!("{}.{}.{}", major, minor, patch),
+        _ => panic!("{} does not contain the SUNDIALS sources \
+                     (SUNDIALS_SYS_VENDOR_SOURCE).", Path::new(&dir).display()),
+    }
+}
+
+/// Directory of the SUNDIALS sources to build:
+/// `SUNDIALS_SYS_VENDOR_SOURCE` (e.g. an unpacked release of SUNDIALS
+/// 6) or the `vendor` submodule.
+fn vendor_source() -> PathBuf {
+    if let Some(dir) = env::var_os("SUNDIALS_SYS_VENDOR_SOURCE") {
+        return PathBuf::from(dir)
+    }
+    if !Path::new("vendor/CMakeLists.txt").is_file() {
+        panic!("The SUNDIALS sources are missing: check out the `vendor` \
+                submodule (`git submodule update --init`).");
+    }
+    PathBuf::from("vendor")
+}
+
+/// Whether the build must not access the network: with the feature
+/// `offline`, `SUNDIALS_SYS_OFFLINE` set (but not to 0) or
+/// `CARGO_NET_OFFLINE=true`.
+fn offline() -> bool {
+    cfg!(feature = "offline")
+        || env::var("SUNDIALS_SYS_OFFLINE").is_ok_and(|v| v != "0")
+        || env::var("CARGO_NET_OFFLINE").is_ok_and(|v| v == "true")
+}
+
+/// Build the Sundials code vendor with sundials-sys.  Return the
+/// library location, its type, and where it comes from.
+fn build_vendor_sundials(deps: &Deps) -> (Library, &'static str, &'static str) {
     macro_rules! feature {
         ($s:tt) => {
             if cfg!(feature = $s) {
@@ -82,228 +800,2294 @@ fn build_vendor_sundials(klu: &Library) -> (Library, &'static str) {
             } else {
                 "OFF"
             }
-        };
+        };
+    }
+
+    // The sanitizer runtime is linked statically into the Rust
+    // executable, so instrumented SUNDIALS libraries must be static too.
+    let sanitizers = sanitizers();
+    // Static musl executables cannot load shared libraries either.
+    let static_libraries = if sanitizers.is_empty() && !static_musl()
+        && !is_wasm() && !is_mobile()
+    {
+        feature!("static_libraries")
+    } else {
+        "ON"
+    };
+    let (shared_libraries, library_type) = match static_libraries {
+        "ON" => ("OFF", "static"),
+        "OFF" => ("ON", "dylib"),
+        _ => unreachable!(),
+    };
+
+    let source = vendor_source();
+    let mut config = cmake::Config::new(&source);
+    if static_musl() {
+        // Only look for static third party libraries (LAPACK,...).
+        config.define("CMAKE_FIND_LIBRARY_SUFFIXES", ".a");
+    }
+    config
+        .define("CMAKE_INSTALL_BINDIR", "lib") // v7.1.0
+        .define("CMAKE_INSTALL_LIBDIR", "lib") // ≤ v7.0.0
+        .define("BUILD_STATIC_LIBS", static_libraries)
+        .define("BUILD_SHARED_LIBS", shared_libraries)
+        .define("BUILD_TESTING", "OFF")
+        .define("SUNDIALS_PRECISION",
+                requested_precision().unwrap_or("double"))
+        .define("SUNDIALS_INDEX_SIZE",
+                requested_index_size().unwrap_or(64).to_string())
+        .define("EXAMPLES_INSTALL", "OFF")
+        .define("EXAMPLES_ENABLE_C", "OFF")
+        .define("BUILD_ARKODE", feature!("arkode"))
+        .define("BUILD_CVODE", feature!("cvode"))
+        .define("BUILD_CVODES", feature!("cvodes"))
+        .define("BUILD_IDA", feature!("ida"))
+        .define("BUILD_IDAS", feature!("idas"))
+        .define("BUILD_KINSOL", feature!("kinsol"))
+        .define("ENABLE_KLU", feature!("klu"))
+        .define("ENABLE_LAPACK", feature!("lapack"))
+        .define("ENABLE_SUPERLUMT", feature!("superlumt"))
+        .define("ENABLE_SUPERLUDIST", feature!("superludist"))
+        .define("ENABLE_MAGMA", feature!("magma"))
+        .define("ENABLE_ONEMKL", feature!("onemkl"))
+        .define("ENABLE_PETSC", feature!("petsc"))
+        .define("ENABLE_HYPRE", feature!("hypre"))
+        .define("ENABLE_TRILINOS", feature!("trilinos"))
+        .define("ENABLE_XBRAID", feature!("xbraid"))
+        .define("OPENMP_ENABLE", feature!("nvecopenmp"))
+        .define("ENABLE_OPENMP_DEVICE", feature!("nvecopenmpdev"))
+        .define("PTHREAD_ENABLE", feature!("nvecpthreads"))
+        .define("ENABLE_MPI", feature!("nvecparallel"))
+        .define("ENABLE_CUDA", feature!("nveccuda"))
+        .define("ENABLE_HIP", feature!("nvechip"))
+        .define("ENABLE_SYCL", feature!("nvecsycl"))
+        .define("ENABLE_RAJA", feature!("nvecraja"))
+        .define("ENABLE_KOKKOS", feature!("kokkos"))
+        .define("ENABLE_KOKKOS_KERNELS", feature!("kokkos"))
+        .define("ENABLE_GINKGO", feature!("ginkgo"))
+        .define("BUILD_NVECTOR_MANYVECTOR", feature!("nvecmanyvector"))
+        .define("BUILD_NVECTOR_MPIMANYVECTOR", feature!("nvecmpimanyvector"))
+        .define("BUILD_NVECTOR_MPIPLUSX", feature!("nvecmpiplusx"))
+        .define("BUILD_SUNMATRIX_CUSPARSE", feature!("cusparse"))
+        .define("BUILD_SUNLINSOL_CUSOLVERSP", feature!("cusolversp"))
+        .define("SUNDIALS_BUILD_WITH_MONITORING", feature!("monitoring"))
+        .define("SUNDIALS_BUILD_WITH_PROFILING", feature!("profiling"))
+        .define("ENABLE_CALIPER", feature!("caliper"));
+    if cfg!(feature = "nvecparallel") {
+        config.define("MPI_C_COMPILER", mpicc());
+    }
+    if cfg!(feature = "nveccuda") {
+        let cuda = cuda_home();
+        config.define("CUDAToolkit_ROOT", &cuda)
+            .define("CMAKE_CUDA_COMPILER", cuda.join("bin").join("nvcc"));
+        // E.g. "80" for A100, "native" for the GPU of this machine.
+        if let Ok(archs) = env::var("SUNDIALS_SYS_CUDA_ARCHITECTURES") {
+            config.define("CMAKE_CUDA_ARCHITECTURES", archs);
+        }
+    }
+    if cfg!(feature = "nvechip") {
+        let rocm = rocm_home();
+        config.define("ROCM_PATH", &rocm)
+            .define("HIP_PATH", &rocm)
+            .define("CMAKE_PREFIX_PATH", &rocm);
+        // E.g. "gfx90a" for MI200.
+        if let Ok(targets) = env::var("SUNDIALS_SYS_AMDGPU_TARGETS") {
+            config.define("AMDGPU_TARGETS", targets);
+        }
+    }
+    if cfg!(feature = "nvecsycl") {
+        let cxx = env::var("SUNDIALS_SYS_SYCL_CXX").unwrap_or("icpx".into());
+        config.define("CMAKE_CXX_COMPILER", cxx).cxxflag("-fsycl");
+    }
+    if cfg!(feature = "kokkos") {
+        for root in ["Kokkos_ROOT", "KokkosKernels_ROOT"] {
+            if let Ok(dir) = env::var(root) {
+                config.define(root, dir);
+            }
+        }
+    }
+    if cfg!(feature = "ginkgo") {
+        // The reference backend and those of the enabled vectors.
+        let mut backends = vec!["REF"];
+        macro_rules! backend { ($($f:tt => $b:expr),*) => {
+            $(if cfg!(feature = $f) { backends.push($b) })*
+        }}
+        backend!("nvecopenmp" => "OMP", "nveccuda" => "CUDA",
+                 "nvechip" => "HIP", "nvecsycl" => "SYCL");
+        config.define("SUNDIALS_GINKGO_BACKENDS", backends.join(";"));
+        if let Ok(dir) = env::var("Ginkgo_ROOT") {
+            config.define("Ginkgo_ROOT", dir);
+        }
+    }
+    if cfg!(feature = "nvecraja") {
+        config.define("SUNDIALS_RAJA_BACKENDS", raja_backend().to_uppercase());
+        if let Ok(dir) = env::var("RAJA_DIR") {
+            config.define("RAJA_DIR", dir);
+        }
+    }
+    let Deps { klu, superlumt, superludist, magma, onemkl, hypre, .. } = deps;
+    if let Some(inc) = &klu.inc {
+        config.define("KLU_INCLUDE_DIR", inc);
+    }
+    if cfg!(feature = "superlumt") {
+        config.define("SUPERLUMT_THREAD_TYPE", superlumt_thread_type());
+        if let Some(inc) = &superlumt.inc {
+            config.define("SUPERLUMT_INCLUDE_DIR", inc);
+        }
+        if let Some(lib) = &superlumt.lib {
+            config.define("SUPERLUMT_LIBRARY_DIR", lib);
+        }
+    }
+    if cfg!(feature = "superludist") {
+        if let Some(inc) = &superludist.inc {
+            config.define("SUPERLUDIST_INCLUDE_DIR", inc);
+        }
+        if let Some(lib) = &superludist.lib {
+            config.define("SUPERLUDIST_LIBRARY_DIR", lib);
+        }
+        // The ParMETIS, METIS and BLAS libraries SuperLU_DIST depends
+        // on, as a `;` separated list.
+        if let Ok(libs) = env::var("SUPERLUDIST_LIBRARIES") {
+            config.define("SUPERLUDIST_LIBRARIES", libs);
+        }
+        if let Ok(openmp) = env::var("SUPERLUDIST_OPENMP") {
+            config.define("SUPERLUDIST_OpenMP", openmp);
+        }
+    }
+    if cfg!(feature = "magma") {
+        config.define("SUNDIALS_MAGMA_BACKENDS", magma_backend().to_uppercase());
+        if let Ok(dir) = env::var("MAGMA_DIR") {
+            config.define("MAGMA_DIR", dir);
+        }
+    }
+    if cfg!(feature = "onemkl") {
+        config.define("ONEMKL_DIR", mkl_home());
+    }
+    if cfg!(feature = "trilinos") {
+        if let Ok(dir) = env::var("Trilinos_DIR") {
+            config.define("Trilinos_DIR", dir);
+        }
+    }
+    if cfg!(feature = "xbraid") {
+        if let Ok(dir) = env::var("XBRAID_DIR") {
+            config.define("XBRAID_DIR", dir);
+        }
+    }
+    if let Some(level) = requested_logging_level() {
+        config.define("SUNDIALS_LOGGING_LEVEL", level.to_string());
+    }
+    if cfg!(feature = "caliper") {
+        if let Ok(dir) = env::var("CALIPER_DIR") {
+            config.define("CALIPER_DIR", dir);
+        }
+        // Adiak (SUNDIALS ≥ 7.0) records metadata about the runs.
+        if let Ok(dir) = env::var("adiak_DIR") {
+            config.define("ENABLE_ADIAK", "ON").define("adiak_DIR", dir);
+        }
+    }
+    if cfg!(feature = "hypre") {
+        if let Some(inc) = &hypre.inc {
+            config.define("HYPRE_INCLUDE_DIR", inc);
+        }
+        if let Some(lib) = &hypre.lib {
+            config.define("HYPRE_LIBRARY_DIR", lib);
+        }
+    }
+    if cfg!(feature = "petsc") {
+        for var in ["PETSC_DIR", "PETSC_ARCH"] {
+            if let Ok(value) = env::var(var) {
+                config.define(var, value);
+            }
+        }
+    }
+    if cfg!(feature = "lapack") {
+        if let Some(vendor) = blas_vendor() {
+            config.define("BLA_VENDOR", vendor);
+        }
+        // Explicit libraries (e.g. "/opt/lib/liblapack.so;/opt/lib/libblas.so").
+        if let Ok(libs) = env::var("LAPACK_LIBRARIES") {
+            config.define("LAPACK_LIBRARIES", libs);
+        }
+    }
+    let target = env::var("TARGET").unwrap_or_default();
+    if is_armv7_hf(&target) {
+        // Same ABI as the Rust target (VFPv3-D16, hard float).
+        config.cflag("-march=armv7-a")
+            .cflag("-mfpu=vfpv3-d16")
+            .cflag("-mfloat-abi=hard");
+    }
+    // `cmake` passes `CMAKE_TOOLCHAIN_FILE` (possibly suffixed by the
+    // target) from the environment itself.
+    if let Ok(file) = env::var("SUNDIALS_SYS_TOOLCHAIN_FILE") {
+        config.define("CMAKE_TOOLCHAIN_FILE", file);
+    } else if env::var("CMAKE_TOOLCHAIN_FILE").is_err()
+        && env::var(format!("CMAKE_TOOLCHAIN_FILE_{}", target)).is_err()
+        && env::var(format!("CMAKE_TOOLCHAIN_FILE_{}",
+                            target.replace('-', "_"))).is_err()
+    {
+        // Without a toolchain file, CMake must still know that it
+        // cross compiles (compilers are taken from `CC`,... as usual).
+        if let Some(file) = wasm_toolchain().map(|(file, _)| file) {
+            config.define("CMAKE_TOOLCHAIN_FILE", file);
+        } else if let Some(ndk) = android_ndk() {
+            config.define("CMAKE_TOOLCHAIN_FILE",
+                          ndk.join("build/cmake/android.toolchain.cmake"))
+                .define("ANDROID_ABI", android_abi())
+                .define("ANDROID_PLATFORM", format!("android-{}", android_api()));
+        } else if let Some((system, processor)) = cmake_system(&target) {
+            if let Some(sdk) = apple_mobile_sdk() {
+                let arch = if processor == "aarch64" { "arm64" } else { &processor };
+                config.define("CMAKE_OSX_SYSROOT", sdk)
+                    .define("CMAKE_OSX_ARCHITECTURES", arch);
+                if let Ok(version) = env::var("IPHONEOS_DEPLOYMENT_TARGET") {
+                    config.define("CMAKE_OSX_DEPLOYMENT_TARGET", version);
+                }
+            }
+            config.define("CMAKE_SYSTEM_NAME", system)
+                .define("CMAKE_SYSTEM_PROCESSOR", processor);
+        }
+    }
+    if let Some(lib) = &klu.lib {
+        config.define("KLU_LIBRARY_DIR", lib);
+    }
+    let msvc = env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|e| e == "msvc");
+    // `cmake` takes the compilers and their flags from the environment
+    // as the `cc` crate does, but only passes the compilers to CMake
+    // on Unix hosts.
+    let compiler_env = compiler_env();
+    if !msvc && env::consts::FAMILY == "windows" {
+        for (kind, tool) in [("C", "CC"), ("CXX", "CXX")] {
+            let set = compiler_env.iter().any(|(name, _)| {
+                name == tool || name.starts_with(&format!("{}_", tool))
+                    || *name == format!("TARGET_{}", tool)
+            });
+            if set {
+                let compiler = cc::Build::new().cargo_metadata(false)
+                    .cpp(kind == "CXX").get_compiler();
+                config.define(format!("CMAKE_{}_COMPILER", kind),
+                              compiler.path());
+            }
+        }
+    }
+    // The Visual Studio generators ignore compiler launchers.
+    let vs = msvc && !env::var("CMAKE_GENERATOR").is_ok_and(|g| g.contains("Ninja"));
+    if let Some(launcher) = compiler_launcher().filter(|_| !vs) {
+        for lang in ["C", "CXX", "CUDA"] {
+            config.define(format!("CMAKE_{}_COMPILER_LAUNCHER", lang), &launcher);
+        }
+    }
+    if !sanitizers.is_empty() {
+        if msvc {
+            // MSVC only knows about the address sanitizer.
+            config.cflag("/fsanitize=address");
+        } else {
+            for san in &sanitizers {
+                config.cflag(format!("-fsanitize={}", san));
+            }
+            config.cflag("-fno-omit-frame-pointer");
+        }
+    }
+
+    // Rust links the release C runtime, dynamically unless the target
+    // feature `crt-static` is enabled, while CMake uses the debug one
+    // (/MDd) in Debug builds: mixing them fails at link time.
+    let crt = msvc_runtime();
+    if let Some(crt) = &crt {
+        config.static_crt(crt.starts_with("MT"))
+            .define("CMAKE_POLICY_DEFAULT_CMP0091", "NEW")
+            .define("CMAKE_MSVC_RUNTIME_LIBRARY", match crt.as_str() {
+                "MT" => "MultiThreaded",
+                "MTd" => "MultiThreadedDebug",
+                "MDd" => "MultiThreadedDebugDLL",
+                _ => "MultiThreadedDLL",
+            });
+    }
+    if cfg!(feature = "fast_math") {
+        if msvc {
+            config.cflag("/fp:fast");
+        } else {
+            config.cflag("-ffast-math");
+        }
+    }
+    if offline() {
+        // Modules fetched by CMake (e.g. googletest, if the SUNDIALS
+        // unit tests are enabled with SUNDIALS_SYS_CMAKE_ARGS) must
+        // already be available.
+        config.define("FETCHCONTENT_FULLY_DISCONNECTED", "ON");
+    }
+    // Flags for linking the shared libraries.
+    let mut ldflags = vec![];
+    if cfg!(feature = "reproducible") {
+        // No absolute paths nor timestamps in the libraries.
+        let source = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+        let build = out_dir.ancestors().nth(2).unwrap_or(&out_dir);
+        let epoch = env::var("SOURCE_DATE_EPOCH").unwrap_or("0".into());
+        config.env("SOURCE_DATE_EPOCH", &epoch);
+        if msvc {
+            config.cflag("/Brepro");
+            ldflags.push("/Brepro".to_string());
+        } else {
+            config.cflag(format!("-ffile-prefix-map={}=sundials-sys", source))
+                .cflag(format!("-ffile-prefix-map={}=build", build.display()));
+            if env::var("CARGO_CFG_TARGET_VENDOR").is_ok_and(|v| v == "apple") {
+                config.env("ZERO_AR_DATE", "1");
+            } else {
+                // Deterministic archives (zero timestamps, uids,...).
+                config.define("CMAKE_C_ARCHIVE_CREATE",
+                              "<CMAKE_AR> qcD <TARGET> <LINK_FLAGS> <OBJECTS>")
+                    .define("CMAKE_C_ARCHIVE_APPEND",
+                            "<CMAKE_AR> qD <TARGET> <LINK_FLAGS> <OBJECTS>")
+                    .define("CMAKE_C_ARCHIVE_FINISH", "<CMAKE_RANLIB> -D <TARGET>");
+            }
+        }
+    }
+    // Profile guided optimization: build instrumented libraries with
+    // SUNDIALS_SYS_PGO_GENERATE=<dir>, run a representative workload,
+    // then rebuild with SUNDIALS_SYS_PGO_USE=<profile> (the directory
+    // for GCC, the merged .profdata file for Clang).
+    let pgo_generate = env::var("SUNDIALS_SYS_PGO_GENERATE").ok();
+    let pgo_use = env::var("SUNDIALS_SYS_PGO_USE").ok();
+    if msvc && (pgo_generate.is_some() || pgo_use.is_some()) {
+        println!("cargo:warning=PGO of the vendored SUNDIALS is not \
+                  supported with MSVC, ignored.");
+    } else if let Some(dir) = &pgo_generate {
+        let flag = format!("-fprofile-generate={}", dir);
+        config.cflag(&flag);
+        ldflags.push(flag);
+        if static_libraries == "ON" {
+            // The profiling runtime must end up in the executable.
+            let cc = cc::Build::new().cargo_metadata(false).get_compiler();
+            if cc.is_like_gnu() {
+                println!("cargo:rustc-link-lib=gcov");
+            } else {
+                println!("cargo:warning=Instrumented static SUNDIALS: also \
+                          use -Cprofile-generate for Rust to link the \
+                          profiling runtime.");
+            }
+        }
+    } else if let Some(profile) = &pgo_use {
+        config.cflag(format!("-fprofile-use={}", profile));
+    }
+    if cfg!(feature = "minsize") {
+        config.profile("MinSizeRel");
+        if msvc {
+            config.cflag("/Os").cflag("/Gy").cflag("/Zc:inline");
+        } else {
+            config.cflag("-Os")
+                .cflag("-g0")
+                .cflag("-ffunction-sections")
+                .cflag("-fdata-sections");
+            let apple = env::var("CARGO_CFG_TARGET_VENDOR")
+                .is_ok_and(|v| v == "apple");
+            // Static archives need their symbols: only the shared
+            // libraries are stripped.
+            let strip = if apple { "-Wl,-dead_strip" } else { "-s" };
+            ldflags.push(strip.to_string());
+        }
+    }
+    if !ldflags.is_empty() {
+        config.define("CMAKE_SHARED_LINKER_FLAGS", ldflags.join(" "));
+    }
+    // Last, so that they override the options set above.
+    let cmake_args = cmake_args();
+    for arg in &cmake_args {
+        match arg.strip_prefix("-D").and_then(|d| d.split_once('=')) {
+            Some((name, value)) => { config.define(name, value); }
+            None => { config.configure_arg(arg); }
+        }
+    }
+
+    // Share the CMake tree between the builds which only differ by the
+    // (additive) set of SUNDIALS modules: CMake then only reconfigures
+    // and compiles what changed instead of starting from scratch in
+    // each new OUT_DIR.
+    let key = format!("{:?}", (static_libraries, &sanitizers,
+        (cfg!(feature = "minsize"), cfg!(feature = "fast_math"),
+         requested_precision(), requested_index_size(),
+         requested_logging_level(), vendor_version(),
+         env::var("SUNDIALS_SYS_VENDOR_SOURCE")),
+        (cfg!(feature = "reproducible"), env::var("SOURCE_DATE_EPOCH")),
+        (&pgo_generate, &pgo_use, &cmake_args, &crt), env::var("TARGET"),
+        env::var("OPT_LEVEL"), env::var("DEBUG"), env::var("PROFILE"),
+        &compiler_env));
+    let _lock;
+    let mut shared = None;
+    if let Some(dir) = shared_dir("cmake", &key) {
+        _lock = DirLock::acquire(&dir);
+        config.out_dir(&dir);
+        shared = Some(dir);
+    }
+    // An identical build (e.g., from another crate of the dependency
+    // graph with the same features) is not redone: the key of the
+    // last completed build is recorded in a stamp file.
+    let modules = [
+        feature!("arkode"), feature!("cvode"), feature!("cvodes"),
+        feature!("ida"), feature!("idas"), feature!("kinsol"),
+        feature!("klu"), feature!("nvecopenmp"), feature!("nvecpthreads"),
+        feature!("nvecparallel"), feature!("nveccuda"), feature!("nvechip"),
+        feature!("nvecsycl"), feature!("nvecraja"), feature!("kokkos"),
+        feature!("nvecopenmpdev"), feature!("nvecmanyvector"),
+        feature!("nvecmpimanyvector"), feature!("nvecmpiplusx"),
+        feature!("lapack"), feature!("superlumt"), feature!("superludist"),
+        feature!("magma"), feature!("onemkl"), feature!("ginkgo"),
+        feature!("cusparse"), feature!("cusolversp"), feature!("petsc"),
+        feature!("hypre"), feature!("trilinos"), feature!("xbraid"),
+        feature!("monitoring"), feature!("profiling"), feature!("caliper")];
+    let build_key = sha256_hex(&[
+        format!("{:?}", (&key, modules, &klu.inc, &klu.lib)).into_bytes(),
+        format!("{:?}", (blas_vendor(), env::var("LAPACK_LIBRARIES").ok(),
+                         &superlumt.inc, &superlumt.lib,
+                         superlumt_thread_type())).into_bytes(),
+        format!("{:?}", (&superludist.inc, &superludist.lib,
+                         env::var("SUPERLUDIST_LIBRARIES").ok(),
+                         env::var("SUPERLUDIST_OPENMP").ok())).into_bytes(),
+        format!("{:?}", (&magma.inc, &magma.lib, &onemkl.inc,
+                         env::var("PETSC_DIR").ok(),
+                         env::var("PETSC_ARCH").ok())).into_bytes(),
+        format!("{:?}", (&hypre.inc, &hypre.lib, env::var("Trilinos_DIR").ok(),
+                         env::var("XBRAID_DIR").ok())).into_bytes(),
+        format!("{:?}", (env::var("CALIPER_DIR").ok(),
+                         env::var("adiak_DIR").ok(),
+                         env::var("SUNDIALS_SYS_LINK_MODULES").ok()))
+            .into_bytes(),
+        fs::read(source.join("CMakeLists.txt")).unwrap_or_default(),
+    ]);
+    let stamp = shared.as_ref().map(|d| d.join("sundials-sys.stamp"));
+    let done = stamp.as_ref()
+        .and_then(|s| fs::read_to_string(s).ok())
+        .is_some_and(|s| s == build_key);
+
+    // SUNDIALS has no CMake option to turn off the native matrices,
+    // linear and nonlinear solvers, but each library is a target.  When
+    // the libraries to link are restricted (SUNDIALS_SYS_LINK_MODULES),
+    // only their targets (and what they depend on) are built, instead
+    // of `install`, so e.g. CVODE with the dense solver does not
+    // compile the Krylov and sparse modules.
+    let targets: Option<Vec<String>> =
+        env::var_os("SUNDIALS_SYS_LINK_MODULES").map(|_| {
+            let kind = if static_libraries == "ON" { "static" } else { "shared" };
+            let major = vendor_version().split('.').next()
+                .and_then(|m| m.parse().ok()).unwrap_or(7);
+            library_names(major).iter()
+                .map(|name| format!("sundials_{}_{}", name, kind))
+                .collect()
+        });
+    let out = shared.clone()
+        .unwrap_or_else(|| PathBuf::from(env::var("OUT_DIR").unwrap()));
+    if targets.is_some() {
+        // All the libraries in `lib`, as `install` does (`$<0:>` keeps
+        // multi-config generators from adding a configuration directory).
+        let lib = format!("{}/lib$<0:>", out.display());
+        for kind in ["ARCHIVE", "LIBRARY", "RUNTIME"] {
+            config.define(format!("CMAKE_{}_OUTPUT_DIRECTORY", kind), &lib);
+        }
+    }
+
+    let dst = match (&shared, done) {
+        (Some(dir), true) => dir.clone(),
+        _ => {
+            // `cmake` uses the jobserver of cargo (with Makefiles) or
+            // builds with `--parallel $NUM_JOBS`.  The arguments of the
+            // native build tool come last, so its `-j` (`/m` for
+            // MSBuild) overrides them.
+            if let Some(jobs) = build_jobs() {
+                let msbuild = env::var("CMAKE_GENERATOR")
+                    .map_or(msvc, |g| g.starts_with("Visual Studio"));
+                config.build_arg(if msbuild { format!("/m:{}", jobs) }
+                                 else { format!("-j{}", jobs) });
+            }
+            let dst = match &targets {
+                Some(targets) => {
+                    for target in targets {
+                        config.build_target(target).build();
+                    }
+                    // The headers of the sources and the generated ones
+                    // (`sundials_config.h`, `sundials_export.h`).
+                    let inc = out.join("include");
+                    copy_dir(&source.join("include"), &inc);
+                    copy_dir(&out.join("build").join("include"), &inc);
+                    out.clone()
+                }
+                None => config.build(),
+            };
+            if let Some(stamp) = &stamp {
+                let _ = fs::write(stamp, &build_key);
+            }
+            dst
+        }
+    };
+    let dst_disp = dst.display();
+    // Installed (or copied) there whatever the build configuration
+    // (`Debug`, `Release`,... sub-directories of the build tree with
+    // the Visual Studio generators).
+    let lib_loc = Some(format!("{}/lib", dst_disp));
+    let inc_dir = Some(format!("{}/include", dst_disp));
+    (Library { inc: inc_dir, lib: lib_loc }, library_type, "vendor")
+}
+
+/// Whether the target is musl with a statically linked C library
+/// (the default for Rust's musl targets), producing executables that
+/// depend on no shared library.
+fn static_musl() -> bool {
+    let features = env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default();
+    env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|e| e == "musl")
+        && features.split(',').any(|f| f == "crt-static")
+}
+
+/// Number of parallel jobs of the vendored build, overriding the
+/// ones of cargo: `SUNDIALS_SYS_BUILD_JOBS`.
+fn build_jobs() -> Option<usize> {
+    let jobs = env::var("SUNDIALS_SYS_BUILD_JOBS").ok()?;
+    match jobs.parse() {
+        Ok(n) if n > 0 => Some(n),
+        _ => panic!("SUNDIALS_SYS_BUILD_JOBS={}: expected a positive \
+                     number of jobs", jobs),
+    }
+}
+
+/// Compiler cache to launch the C compilers of the vendored build
+/// with: `SUNDIALS_SYS_COMPILER_LAUNCHER` ("none" to disable), else
+/// `SCCACHE` or `CCACHE` (the path of the program, or "1" to take it
+/// from the `PATH`), else sccache if it is the `RUSTC_WRAPPER`.
+fn compiler_launcher() -> Option<String> {
+    if let Ok(launcher) = env::var("SUNDIALS_SYS_COMPILER_LAUNCHER") {
+        return Some(launcher).filter(|l| l != "none" && !l.is_empty())
+    }
+    for (var, program) in [("SCCACHE", "sccache"), ("CCACHE", "ccache")] {
+        match env::var(var) {
+            Ok(v) if v == "1" || v == "true" => return Some(program.into()),
+            Ok(v) if !v.is_empty() && v != "0" => return Some(v),
+            _ => (),
+        }
+    }
+    env::var("RUSTC_WRAPPER").ok().filter(|w| {
+        Path::new(w).file_stem().is_some_and(|s| s == "sccache")
+    })
+}
+
+/// C runtime of the vendored SUNDIALS with MSVC: "MT", "MTd", "MD" or
+/// "MDd", given by `SUNDIALS_SYS_MSVC_CRT` or, by default, the one
+/// Rust links (`MT` with the target feature `crt-static`, `MD`
+/// otherwise).  `None` for other targets.
+fn msvc_runtime() -> Option<String> {
+    if !env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|e| e == "msvc") {
+        return None
+    }
+    match env::var("SUNDIALS_SYS_MSVC_CRT") {
+        Ok(crt) if ["MT", "MTd", "MD", "MDd"].contains(&crt.as_str()) => Some(crt),
+        Ok(crt) => panic!("SUNDIALS_SYS_MSVC_CRT={}: expected MT, MTd, MD \
+                           or MDd", crt),
+        Err(_) => {
+            let features = env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default();
+            let crt_static = features.split(',').any(|f| f == "crt-static");
+            Some(if crt_static { "MT" } else { "MD" }.to_string())
+        }
+    }
+}
+
+/// `CMAKE_SYSTEM_NAME` and `CMAKE_SYSTEM_PROCESSOR` of the `target`
+/// when it differs from the host, `None` for a native build (or a
+/// target CMake must be given a toolchain file for, like wasm).
+fn cmake_system(target: &str) -> Option<(&'static str, String)> {
+    if env::var("HOST").ok().as_deref() == Some(target) {
+        return None
+    }
+    let var = |v: &str| env::var(v).unwrap_or_default();
+    let system = match var("CARGO_CFG_TARGET_OS").as_str() {
+        "linux" => "Linux",
+        "android" => "Android",
+        "windows" => "Windows",
+        "macos" => "Darwin",
+        "ios" => "iOS",
+        "freebsd" => "FreeBSD",
+        _ => return None,
+    };
+    let processor = match var("CARGO_CFG_TARGET_ARCH").as_str() {
+        "arm" if target.starts_with("armv7") => "armv7l".to_string(),
+        "x86" => "i686".to_string(),
+        arch => arch.to_string(),
+    };
+    Some((system, processor))
+}
+
+/// Whether the target is WebAssembly.
+fn is_wasm() -> bool {
+    env::var("CARGO_CFG_TARGET_FAMILY")
+        .is_ok_and(|f| f.split(',').any(|f| f == "wasm"))
+}
+
+/// CMake toolchain file and sysroot of the wasm SDK for the target:
+/// Emscripten (`EMSDK` or `EMSCRIPTEN`, set by `emsdk_env`) or
+/// wasi-sdk (`WASI_SDK_PREFIX` or `WASI_SDK_PATH`).
+fn wasm_toolchain() -> Option<(PathBuf, PathBuf)> {
+    if !is_wasm() {
+        return None
+    }
+    let target = env::var("TARGET").unwrap_or_default();
+    let (root, file, sysroot) = if target.ends_with("emscripten") {
+        let root = env::var("EMSCRIPTEN").map(PathBuf::from)
+            .or_else(|_| env::var("EMSDK")
+                     .map(|d| Path::new(&d).join("upstream/emscripten")))
+            .ok()?;
+        (root, "cmake/Modules/Platform/Emscripten.cmake", "cache/sysroot")
+    } else if target.contains("wasi") {
+        let root = env::var_os("WASI_SDK_PREFIX")
+            .or_else(|| env::var_os("WASI_SDK_PATH"))?;
+        let file = if target.ends_with("threads") {
+            "share/cmake/wasi-sdk-pthread.cmake"
+        } else {
+            "share/cmake/wasi-sdk.cmake"
+        };
+        (PathBuf::from(root), file, "share/wasi-sysroot")
+    } else {
+        return None
+    };
+    let file = root.join(file);
+    file.is_file().then(|| (file, root.join(sysroot)))
+}
+
+/// Whether the target is Android or iOS, for which SUNDIALS is always
+/// vendored and linked statically into the application.
+fn is_mobile() -> bool {
+    env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "android" || os == "ios")
+}
+
+/// Root of the Android NDK (`ANDROID_NDK_HOME`, `ANDROID_NDK_ROOT`,
+/// `ANDROID_NDK` or `$ANDROID_HOME/ndk-bundle`) when targeting Android.
+fn android_ndk() -> Option<PathBuf> {
+    if !env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "android") {
+        return None
+    }
+    ["ANDROID_NDK_HOME", "ANDROID_NDK_ROOT", "ANDROID_NDK"].iter()
+        .find_map(env::var_os).map(PathBuf::from)
+        .or_else(|| env::var_os("ANDROID_HOME")
+                 .map(|d| Path::new(&d).join("ndk-bundle")))
+        .filter(|d| d.join("build/cmake/android.toolchain.cmake").is_file())
+}
+
+/// Android ABI of the target, as named by the NDK.
+fn android_abi() -> &'static str {
+    match env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default().as_str() {
+        "arm" => "armeabi-v7a",
+        "x86" => "x86",
+        "x86_64" => "x86_64",
+        _ => "arm64-v8a",
+    }
+}
+
+/// Minimum Android API level: `ANDROID_PLATFORM` (e.g. `android-24`
+/// or `24`), `ANDROID_API_LEVEL` or 21.
+fn android_api() -> String {
+    env::var("ANDROID_PLATFORM")
+        .map(|p| p.trim_start_matches("android-").to_string())
+        .or_else(|_| env::var("ANDROID_API_LEVEL"))
+        .unwrap_or("21".into())
+}
+
+/// Xcode SDK of the iOS target: "iphoneos" or, for the simulator,
+/// "iphonesimulator".
+fn apple_mobile_sdk() -> Option<&'static str> {
+    if !env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "ios") {
+        return None
+    }
+    let target = env::var("TARGET").unwrap_or_default();
+    let sim = target.ends_with("-sim") || target.starts_with("x86_64");
+    Some(if sim { "iphonesimulator" } else { "iphoneos" })
+}
+
+/// Whether the target is ARMv7 Linux with the hard-float ABI.
+fn is_armv7_hf(target: &str) -> bool {
+    target.starts_with("armv7") && target.ends_with("gnueabihf")
+}
+
+/// Target specific clang arguments for bindgen, so the C types have
+/// the layout they have for the target.  (bindgen itself passes the
+/// clang `--target` corresponding to `TARGET`.)  When cross
+/// compiling, the system headers of the target are taken from
+/// `SUNDIALS_SYS_SYSROOT`, the sysroot of the C compiler for the
+/// target or, for Debian/Ubuntu cross toolchains (e.g.
+/// `aarch64-linux-gnu-gcc`), `/usr/<prefix>/include`.
+fn target_clang_args() -> Vec<String> {
+    let target = env::var("TARGET").unwrap_or_default();
+    let host = env::var("HOST").unwrap_or_default();
+    let mut args = vec![];
+    if is_armv7_hf(&target) {
+        args.push("--target=armv7-unknown-linux-gnueabihf".to_string());
+        args.push("-mfloat-abi=hard".to_string());
+        args.push("-mfpu=vfpv3-d16".to_string());
+    }
+    if target == host {
+        return args
+    }
+    if let Ok(sysroot) = env::var("SUNDIALS_SYS_SYSROOT") {
+        args.push(format!("--sysroot={}", sysroot));
+        return args
+    }
+    if is_wasm() {
+        // Otherwise given with `BINDGEN_EXTRA_CLANG_ARGS`.
+        if let Some((_, sysroot)) = wasm_toolchain() {
+            args.push(format!("--sysroot={}", sysroot.display()));
+        }
+        return args
+    }
+    if let Some(ndk) = android_ndk() {
+        // <ndk>/toolchains/llvm/prebuilt/<host tag>/sysroot
+        let prebuilt = ndk.join("toolchains/llvm/prebuilt");
+        if let Some(host) = fs::read_dir(&prebuilt).ok()
+            .and_then(|mut d| d.find_map(|e| e.ok()))
+        {
+            args.push(format!("--sysroot={}",
+                              host.path().join("sysroot").display()));
+        }
+        // The API level selects the declarations of the headers.
+        let triple = target.replace("armv7-", "armv7a-");
+        args.push(format!("--target={}{}", triple, android_api()));
+        return args
+    }
+    if let Some(sdk) = apple_mobile_sdk() {
+        let path = std::process::Command::new("xcrun")
+            .args(["--sdk", sdk, "--show-sdk-path"]).output().ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+        if let Some(path) = path {
+            args.push(format!("-isysroot{}", path));
+        }
+        return args
+    }
+    let cc = cc::Build::new().cargo_metadata(false).get_compiler();
+    let sysroot = std::process::Command::new(cc.path())
+        .arg("-print-sysroot").output().ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|s| !s.is_empty() && s != "/" && Path::new(s).is_dir());
+    if let Some(sysroot) = sysroot {
+        args.push(format!("--sysroot={}", sysroot));
+    } else if let Some(prefix) = cc.path().file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.strip_suffix("-gcc").or(n.strip_suffix("-cc")))
+    {
+        let cross_inc = Path::new("/usr").join(prefix).join("include");
+        if cross_inc.is_dir() {
+            args.push(format!("-isystem{}", cross_inc.display()));
+        }
+    }
+    args
+}
+
+/// Root of the CUDA toolkit: `CUDA_PATH`, `CUDA_HOME`, the parent of
+/// the directory of `nvcc` in the `PATH` or `/usr/local/cuda`.
+fn cuda_home() -> PathBuf {
+    if let Some(home) = ["CUDA_PATH", "CUDA_HOME"].iter()
+        .find_map(env::var_os)
+    {
+        return PathBuf::from(home)
+    }
+    let nvcc = env::var_os("PATH").and_then(|path| {
+        env::split_paths(&path).find(|dir| {
+            dir.join("nvcc").is_file() || dir.join("nvcc.exe").is_file()
+        })
+    });
+    nvcc.as_deref().and_then(Path::parent).map(Path::to_path_buf)
+        .unwrap_or("/usr/local/cuda".into())
+}
+
+/// Directory of the CUDA libraries for the target.
+fn cuda_lib_dir() -> PathBuf {
+    if env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "windows") {
+        cuda_home().join("lib").join("x64")
+    } else {
+        cuda_home().join("lib64")
+    }
+}
+
+/// Root of the ROCm installation (`ROCM_PATH`, by default `/opt/rocm`).
+fn rocm_home() -> PathBuf {
+    env::var_os("ROCM_PATH").map(PathBuf::from)
+        .unwrap_or("/opt/rocm".into())
+}
+
+/// Root of the oneAPI DPC++ compiler (`CMPLR_ROOT`, set by the oneAPI
+/// `setvars` script, by default `/opt/intel/oneapi/compiler/latest`).
+fn sycl_home() -> PathBuf {
+    env::var_os("CMPLR_ROOT").map(PathBuf::from)
+        .unwrap_or("/opt/intel/oneapi/compiler/latest".into())
+}
+
+/// Root of oneMKL (`MKLROOT`, set by the oneAPI `setvars` script, by
+/// default `/opt/intel/oneapi/mkl/latest`).
+fn mkl_home() -> PathBuf {
+    env::var_os("MKLROOT").map(PathBuf::from)
+        .unwrap_or("/opt/intel/oneapi/mkl/latest".into())
+}
+
+/// Location of oneMKL, if the feature `onemkl` is enabled.
+fn onemkl_library() -> Library {
+    if !cfg!(feature = "onemkl") {
+        return Library { inc: None, lib: None }
+    }
+    let mkl = mkl_home();
+    Library {
+        inc: Some(mkl.join("include").display().to_string()),
+        lib: Some(mkl.join("lib").display().to_string()),
+    }
+}
+
+/// Backend ("cuda", "hip" or "sycl") of the RAJA vector, given by
+/// the enabled GPU vector feature.
+fn raja_backend() -> &'static str {
+    if cfg!(feature = "nveccuda") {
+        "cuda"
+    } else if cfg!(feature = "nvechip") {
+        "hip"
+    } else if cfg!(feature = "nvecsycl") {
+        "sycl"
+    } else {
+        panic!("The feature nvecraja requires one of the features \
+                nveccuda, nvechip or nvecsycl (the RAJA backend).")
+    }
+}
+
+/// Backend ("cuda" or "hip") of MAGMA, given by the enabled GPU
+/// vector feature.
+fn magma_backend() -> &'static str {
+    if cfg!(feature = "nveccuda") {
+        "cuda"
+    } else if cfg!(feature = "nvechip") {
+        "hip"
+    } else {
+        panic!("The feature magma requires one of the features nveccuda \
+                or nvechip (the MAGMA backend).")
+    }
+}
+
+/// Location of MAGMA, installed in `MAGMA_DIR`.
+fn magma_library() -> Library {
+    let dir = env::var("MAGMA_DIR").ok();
+    Library {
+        inc: dir.as_ref().map(|d| format!("{}/include", d)),
+        lib: dir.as_ref().map(|d| format!("{}/lib", d)),
+    }
+}
+
+/// BLAS/LAPACK provider, in the naming of CMake's `BLA_VENDOR`: the
+/// `BLA_VENDOR` environment variable, otherwise the one of the
+/// `lapack_*` feature, if any.
+fn blas_vendor() -> Option<String> {
+    if let Ok(vendor) = env::var("BLA_VENDOR") {
+        Some(vendor)
+    } else if cfg!(feature = "lapack_openblas") {
+        Some("OpenBLAS".into())
+    } else if cfg!(feature = "lapack_mkl") {
+        Some("Intel10_64lp".into())
+    } else if cfg!(feature = "lapack_accelerate") {
+        Some("Apple".into())
+    } else {
+        None
+    }
+}
+
+/// Link directives for the BLAS/LAPACK libraries used by the static
+/// SUNDIALS LAPACK linear solvers.
+fn lapack_directives() -> Vec<String> {
+    let mut directives = vec![];
+    if let Ok(dir) = env::var("LAPACK_LIB_DIR") {
+        directives.push(format!("rustc-link-search=native={}", dir));
+    }
+    let libs: &[&str] = match blas_vendor().as_deref() {
+        Some("OpenBLAS") => &["openblas"],
+        Some(v) if v.starts_with("Intel") => &["mkl_rt"],
+        Some("Apple" | "NAS") => &["framework=Accelerate"],
+        _ => &["lapack", "blas"],
+    };
+    directives.extend(libs.iter().map(|l| format!("rustc-link-lib={}", l)));
+    directives
+}
+
+/// Whether SUNDIALS is linked statically on Windows.  Its headers
+/// then need `SUNDIALS_STATIC_DEFINE`, otherwise the functions are
+/// declared `__declspec(dllimport)` and the link fails.
+fn static_define(library_type: &str) -> bool {
+    (library_type == "static" || cfg!(feature = "static_libraries"))
+        && env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "windows")
+}
+
+/// Header files to generate the bindings for.  Only the ones of the
+/// enabled features are listed, so that disabled modules do not cost
+/// any bindgen time nor make bindings.rs larger.
+fn headers(inc_dirs: &[Option<String>]) -> Vec<&'static str> {
+    let mut headers = vec![
+        "sundials/sundials_version.h",
+        "sundials/sundials_matrix.h",
+        "sundials/sundials_linearsolver.h",
+        "sundials/sundials_nonlinearsolver.h",
+        "nvector/nvector_serial.h",
+    ];
+    // The profiler appeared in SUNDIALS 6.0, the logger in 6.2.
+    for h in ["sundials/sundials_profiler.h", "sundials/sundials_logger.h"] {
+        if has_header(inc_dirs, h) {
+            headers.push(h);
+        }
+    }
+    if solvers_enabled() {
+        // Otherwise, only the core: for new modules or the types.
+        headers.extend([
+            "sunlinsol/sunlinsol_band.h",
+            "sunlinsol/sunlinsol_spbcgs.h",
+            "sunlinsol/sunlinsol_spgmr.h",
+            "sunlinsol/sunlinsol_dense.h",
+            "sunlinsol/sunlinsol_pcg.h",
+            "sunlinsol/sunlinsol_spfgmr.h",
+            "sunlinsol/sunlinsol_sptfqmr.h",
+            "sunmatrix/sunmatrix_band.h",
+            "sunmatrix/sunmatrix_dense.h",
+            "sunmatrix/sunmatrix_sparse.h",
+            "sunnonlinsol/sunnonlinsol_fixedpoint.h",
+            "sunnonlinsol/sunnonlinsol_newton.h",
+        ]);
+    }
+    macro_rules! headers { ($($f:tt => [$($h:expr),*]),* $(,)?) => {
+        $(if cfg!(feature = $f) { $(headers.push($h);)* })*
+    }}
+    headers! {
+        "nvecopenmp" => ["nvector/nvector_openmp.h"],
+        "nvecopenmpdev" => ["nvector/nvector_openmpdev.h"],
+        "nvecpthreads" => ["nvector/nvector_pthreads.h"],
+        "nvecparallel" => ["nvector/nvector_parallel.h"],
+        "nveccuda" => ["nvector/nvector_cuda.h"],
+        "cusparse" => ["sunmatrix/sunmatrix_cusparse.h"],
+        "cusolversp" => ["sunlinsol/sunlinsol_cusolversp_batchqr.h"],
+        "nvechip" => ["nvector/nvector_hip.h"],
+        "nvecsycl" => ["nvector/nvector_sycl.h"],
+        "nvecraja" => ["nvector/nvector_raja.h"],
+        "nvecmanyvector" => ["nvector/nvector_manyvector.h"],
+        "nvecmpimanyvector" => ["nvector/nvector_mpimanyvector.h"],
+        "nvecmpiplusx" => ["nvector/nvector_mpiplusx.h"],
+        "petsc" => ["nvector/nvector_petsc.h",
+                    "sunnonlinsol/sunnonlinsol_petscsnes.h"],
+        "hypre" => ["nvector/nvector_parhyp.h"],
+        "trilinos" => ["nvector/nvector_trilinos.h"],
+        "arkode" => ["arkode/arkode_arkstep.h", "arkode/arkode_erkstep.h",
+                     "arkode/arkode_mristep.h"],
+        "xbraid" => ["arkode/arkode_xbraid.h"],
+        "ida" => ["ida/ida.h"],
+        "idas" => ["idas/idas.h"],
+        "kinsol" => ["kinsol/kinsol.h"],
+        "klu" => ["sunlinsol/sunlinsol_klu.h"],
+        "lapack" => ["sunlinsol/sunlinsol_lapackdense.h",
+                     "sunlinsol/sunlinsol_lapackband.h"],
+        "superlumt" => ["sunlinsol/sunlinsol_superlumt.h"],
+        "superludist" => ["sunmatrix/sunmatrix_slunrloc.h",
+                          "sunlinsol/sunlinsol_superludist.h"],
+        "magma" => ["sunmatrix/sunmatrix_magmadense.h",
+                    "sunlinsol/sunlinsol_magmadense.h"],
+        "onemkl" => ["sunmatrix/sunmatrix_onemkldense.h",
+                     "sunlinsol/sunlinsol_onemkldense.h"],
+    }
+    // The CVODES API is a superset of the CVODE one.
+    if cfg!(feature = "cvodes") {
+        headers.push("cvodes/cvodes.h");
+    } else if cfg!(feature = "cvode") {
+        headers.push("cvode/cvode.h");
+    }
+    headers
+}
+
+/// Whether the SUNDIALS whose headers are in `inc_dirs` (or in the
+/// usual system directories) has the header `h`.  If its headers are
+/// not found there (e.g. they are in the default search path of a
+/// cross compiler), `h` is assumed to exist.
+fn has_header(inc_dirs: &[Option<String>], h: &str) -> bool {
+    let system = ["/usr/include", "/usr/local/include"];
+    let sundials = inc_dirs.iter().flatten().map(|d| d.as_str())
+        .chain(system).map(Path::new)
+        .find(|d| d.join("sundials/sundials_config.h").is_file());
+    match sundials {
+        Some(dir) => dir.join(h).is_file(),
+        None => true,
+    }
+}
+
+// Record the files included while generating the bindings.
+#[derive(Debug, Default)]
+struct IncludedFiles(Arc<Mutex<Vec<String>>>);
+
+impl bindgen::callbacks::ParseCallbacks for IncludedFiles {
+    fn include_file(&self, filename: &str) {
+        self.0.lock().unwrap().push(filename.to_string())
+    }
+}
+
+/// Hash of the bindgen configuration and of the content of `files`.
+fn bindings_hash(config: &[String], files: &[&str]) -> String {
+    // The parse callbacks are part of the configuration.
+    let mut parts = vec![include_str!("build.rs").as_bytes().to_vec()];
+    parts.extend(config.iter().map(|c| c.as_bytes().to_vec()));
+    for f in files {
+        parts.push(f.as_bytes().to_vec());
+        parts.push(fs::read(f).unwrap_or_default());
+    }
+    sha256_hex(&parts)
+}
+
+/// Generate the bindings into the file `bindings_rs` and return the
+/// list of header files that were read.  bindgen is skipped if its
+/// configuration and all the headers it read the last time are
+/// unchanged, which is recorded in a stamp file next to
+/// `bindings_rs`: its first line is the hash of the inputs, the
+/// following ones the included files.
+fn generate_bindings(
+    inc_dirs: &[Option<String>], bindings_rs: &Path, static_define: bool,
+) -> Result<Vec<String>, BindgenError> {
+    let wrapper: String = headers(inc_dirs).iter()
+        .map(|h| format!("#include <{}>\n", h))
+        .collect();
+    let mut builder = bindgen::Builder::default()
+        .header_contents("wrapper.h", &wrapper);
+    for dir in inc_dirs.iter().flatten() {
+        builder = builder.clang_arg(format!("-I{}", dir))
+    }
+    for dir in nix_include_dirs() {
+        builder = builder.clang_arg(format!("-isystem{}", dir))
+    }
+    if cfg!(feature = "nvecparallel") || mpi_enabled(inc_dirs) {
+        // SUNComm is then MPI_Comm.
+        for dir in split_flag_args(&mpi_flags(), &["-I"]) {
+            builder = builder.clang_arg(format!("-I{}", dir))
+        }
+    }
+    if cfg!(feature = "nveccuda") {
+        builder = builder.clang_arg(
+            format!("-I{}", cuda_home().join("include").display()));
+    }
+    if cfg!(feature = "nvechip") {
+        builder = builder
+            .clang_arg(format!("-I{}", rocm_home().join("include").display()))
+            .clang_arg("-D__HIP_PLATFORM_AMD__");
+    }
+    if cfg!(any(feature = "nvecsycl", feature = "trilinos")) {
+        builder = builder
+            .clang_args(["-x", "c++", "-std=c++17"])
+            .opaque_type("std::.*");
+    }
+    if cfg!(feature = "nvecsycl") {
+        // The API of the SYCL vector uses `sycl::queue`, hence is C++.
+        let inc = sycl_home().join("include");
+        builder = builder
+            .clang_arg(format!("-I{}", inc.join("sycl").display()))
+            .clang_arg(format!("-I{}", inc.display()))
+            .opaque_type("sycl::.*");
+    }
+    if cfg!(feature = "trilinos") {
+        // The Tpetra vector is held by a `Teuchos::RCP` smart pointer,
+        // passed by value: the functions taking or returning it have
+        // no C ABI and must be called from C++.
+        builder = builder
+            .opaque_type("(Teuchos|Tpetra|Kokkos|Sundials)::.*")
+            .blocklist_function("N_VMake_Trilinos")
+            .blocklist_function("N_VGetVector_Trilinos");
+    }
+    if static_define {
+        builder = builder.clang_arg("-DSUNDIALS_STATIC_DEFINE");
+    }
+    builder = builder.clang_args(target_clang_args());
+    builder = builder.layout_tests(cfg!(feature = "layout_tests"));
+    // Defined in lib.rs as `i32` or `i64` whatever the C integer type
+    // behind `int32_t`/`int64_t` is.
+    builder = builder.blocklist_type("sunindextype");
+    if cfg!(feature = "extended_precision") && long_double_is_wide() {
+        // Rust has no `long double`: `sunrealtype` is defined in lib.rs.
+        builder = builder.blocklist_type("realtype")
+            .blocklist_type("sunrealtype");
+    }
+    if cfg!(feature = "dlopen") {
+        // Functions are members of the `Sundials` struct, resolved when
+        // loading the library at runtime.
+        builder = builder.dynamic_library_name("Sundials");
+    }
+    if !cfg!(feature = "expose_internals") {
+        // Implementation details of the modules (their content) and
+        // the operation tables are only needed to write new modules.
+        builder = builder
+            .opaque_type("_.*Content_.*")
+            .opaque_type("_generic_.*_Ops");
+    }
+    for pattern in allowlist(inc_dirs) {
+        builder = builder.allowlist_function(pattern)
+            .allowlist_type(pattern)
+            .allowlist_var(pattern);
+    }
+    let mut config = builder.command_line_flags();
+    config.push(wrapper);
+    config.push(env::var("TARGET").unwrap_or_default());
+
+    let stamp = bindings_rs.with_extension("stamp");
+    if let Ok(s) = fs::read_to_string(&stamp) {
+        let mut lines = s.lines();
+        let hash = lines.next();
+        let files: Vec<_> = lines.collect();
+        if bindings_rs.exists() && hash == Some(&bindings_hash(&config, &files)) {
+            return Ok(files.into_iter().map(|f| f.to_string()).collect())
+        }
+    }
+
+    let included = IncludedFiles::default();
+    let files = Arc::clone(&included.0);
+    let bindings = builder
+        .parse_callbacks(Box::new(ParseSignedConstants))
+        .parse_callbacks(Box::new(IgnoreMacros::new()))
+        .parse_callbacks(Box::new(included))
+        .generate()?;
+    bindings.write_to_file(bindings_rs)
+        .expect("Couldn't write file bindings.rs!");
+    let files = files.lock().unwrap().clone();
+    let files_str: Vec<&str> = files.iter().map(|f| f.as_str()).collect();
+    let stamp_content = format!("{}\n{}", bindings_hash(&config, &files_str),
+                                files.join("\n"));
+    fs::write(&stamp, stamp_content).expect("Couldn't write bindings stamp!");
+    Ok(files)
+}
+
+/// Patterns of the names to bind: those of SUNDIALS, of the enabled
+/// solvers and of the third party libraries whose objects are created
+/// by the user (rather than everything the headers include, e.g. the
+/// C library).  Items they refer to are bound too.
+fn allowlist(inc_dirs: &[Option<String>]) -> Vec<&'static str> {
+    let mut patterns = vec![
+        "(SUN|sun|N_V).*",
+        "(real|boolean)type",
+        // SUNDIALS 6 names of some constants.
+        "PREC_(NONE|LEFT|RIGHT|BOTH)",
+        "(MODIFIED|CLASSICAL)_GS",
+        "CS[CR]_MAT",
+    ];
+    macro_rules! allow { ($($f:tt => [$($p:expr),*]),* $(,)?) => {
+        $(if cfg!(feature = $f) { $(patterns.push($p);)* })*
+    }}
+    allow! {
+        "arkode" => ["(ARK|ERK|MRI|SPRK|arkode).*"],
+        "cvode" => ["CV.*"],
+        "cvodes" => ["CV.*"],
+        "ida" => ["IDA.*"],
+        "idas" => ["IDA.*"],
+        "kinsol" => ["KIN.*"],
+        "klu" => ["klu_.*"],
+        "magma" => ["magma_.*"],
+        "nveccuda" => ["cuda.*"],
+        "nvechip" => ["hip.*"],
+        "cusparse" => ["cusparse.*"],
+        "cusolversp" => ["cusolver.*"],
+        "petsc" => ["(Petsc|PETSC|Vec|Mat|SNES|KSP|PC)[A-Z_].*"],
+        "hypre" => ["(HYPRE|hypre)_.*"],
+        "xbraid" => ["braid_.*"],
+        "caliper" => ["cali_.*"],
+    }
+    if cfg!(feature = "nvecparallel") || mpi_enabled(inc_dirs) {
+        patterns.push("MPI_.*");
+    }
+    patterns
+}
+
+/// Version of SUNDIALS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+/// `#define`s (name and value) of the file `sundials_config.h`
+/// among the headers `files` included to generate the bindings.
+fn sundials_config(files: &[String]) -> Option<HashMap<String, String>> {
+    let config_h = files.iter().find(|f| {
+        Path::new(f).ends_with("sundials/sundials_config.h")
+    })?;
+    let config_h = fs::read_to_string(config_h).ok()?;
+    let mut defines = HashMap::new();
+    for line in config_h.lines() {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("#define") {
+            continue
+        }
+        if let Some(name) = words.next() {
+            let value = words.next().unwrap_or("").trim_matches('"');
+            defines.insert(name.to_string(), value.to_string());
+        }
+    }
+    Some(defines)
+}
+
+/// The SUNDIALS version according to its configuration `defines`.
+fn get_sundials_version(defines: &HashMap<String, String>) -> Option<Version> {
+    let num = |name: &str| defines.get(name).and_then(|v| v.parse().ok());
+    if let (Some(major), Some(minor), Some(patch)) = (
+        num("SUNDIALS_VERSION_MAJOR"), num("SUNDIALS_VERSION_MINOR"),
+        num("SUNDIALS_VERSION_PATCH"))
+    {
+        return Some(Version { major, minor, patch })
+    }
+    // Older versions only have SUNDIALS_VERSION "x.y.z".
+    let v: Vec<u32> = defines.get("SUNDIALS_VERSION")?.split(['.', '-'])
+        .map_while(|n| n.parse().ok()).collect();
+    match v[..] {
+        [major, minor, patch, ..] => Some(Version { major, minor, patch }),
+        _ => None,
+    }
+}
+
+/// Floating point precision of SUNDIALS ("single", "double" or
+/// "extended") according to its configuration `defines`.
+fn get_precision(defines: &HashMap<String, String>) -> &'static str {
+    if defines.contains_key("SUNDIALS_SINGLE_PRECISION") {
+        "single"
+    } else if defines.contains_key("SUNDIALS_EXTENDED_PRECISION") {
+        "extended"
+    } else {
+        "double"
+    }
+}
+
+/// Floating point precision requested by the features, `None` if
+/// any precision is fine (the vendored SUNDIALS then uses "double").
+fn requested_precision() -> Option<&'static str> {
+    if cfg!(feature = "single_precision") {
+        Some("single")
+    } else if cfg!(feature = "extended_precision") {
+        Some("extended")
+    } else {
+        None
+    }
+}
+
+/// Whether the C `long double` of the target is wider than `double`.
+/// It is the same as `double` with MSVC, on 32 bits ARM and Apple
+/// ARM64.  Only called for SUNDIALS in extended precision, which is
+/// refused on PowerPC: its `long double` is the IBM double-double
+/// format, which `c_longdouble` does not handle.
+fn long_double_is_wide() -> bool {
+    let var = |v: &str| env::var(v).unwrap_or_default();
+    let arch = var("CARGO_CFG_TARGET_ARCH");
+    if arch == "powerpc" || arch == "powerpc64" {
+        panic!("SUNDIALS in extended precision is not supported on {}: \
+                its `long double` (IBM double-double) cannot be converted \
+                (disable the feature `extended_precision`).", arch);
+    }
+    !(var("CARGO_CFG_TARGET_ENV") == "msvc" || arch == "arm"
+      || (var("CARGO_CFG_TARGET_VENDOR") == "apple" && arch == "aarch64"))
+}
+
+/// Size in bits of `sunindextype` according to the configuration
+/// `defines` of SUNDIALS.
+fn get_index_size(defines: &HashMap<String, String>) -> u32 {
+    if defines.contains_key("SUNDIALS_INT32_T") { 32 } else { 64 }
+}
+
+/// Size in bits of `sunindextype` requested by the features, `None`
+/// if any size is fine (the vendored SUNDIALS then uses 64).
+fn requested_index_size() -> Option<u32> {
+    if cfg!(feature = "index_size_32") { Some(32) } else { None }
+}
+
+/// Logging level of SUNDIALS (0: none, 1: errors, 2: warnings,
+/// 3: info, 4: debug, 5: extra debug) according to its configuration
+/// `defines`.
+fn get_logging_level(defines: &HashMap<String, String>) -> u32 {
+    defines.get("SUNDIALS_LOGGING_LEVEL")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Logging level requested by `SUNDIALS_SYS_LOGGING_LEVEL` or the
+/// features, `None` if any level is fine (the vendored SUNDIALS then
+/// uses its default).
+fn requested_logging_level() -> Option<u32> {
+    if let Some(level) = env::var("SUNDIALS_SYS_LOGGING_LEVEL").ok()
+        .and_then(|l| l.parse().ok())
+    {
+        Some(level)
+    } else if cfg!(feature = "logging_debug") {
+        Some(4)
+    } else if cfg!(feature = "logging_info") {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// Optional capabilities of SUNDIALS and the `sundials_config.h`
+/// defines announcing them.  Each capability `c` present in the
+/// library is exposed as the cfg `sundials_has_c`.
+const CAPABILITIES: &[(&str, &[&str])] = &[
+    ("klu", &["SUNDIALS_SUNLINSOL_KLU", "SUNDIALS_KLU_ENABLED"]),
+    ("lapack", &["SUNDIALS_SUNLINSOL_LAPACKDENSE",
+                 "SUNDIALS_SUNLINSOL_LAPACKBAND",
+                 "SUNDIALS_BLAS_LAPACK_ENABLED"]),
+    ("mpi", &["SUNDIALS_MPI_ENABLED", "SUNDIALS_NVECTOR_PARALLEL"]),
+    ("openmp", &["SUNDIALS_NVECTOR_OPENMP", "SUNDIALS_OPENMP_ENABLED"]),
+    ("pthreads", &["SUNDIALS_NVECTOR_PTHREADS", "SUNDIALS_PTHREADS_ENABLED"]),
+    ("superlumt", &["SUNDIALS_SUNLINSOL_SUPERLUMT",
+                    "SUNDIALS_SUPERLUMT_ENABLED"]),
+    ("superludist", &["SUNDIALS_SUNLINSOL_SUPERLUDIST",
+                      "SUNDIALS_SUPERLUDIST_ENABLED"]),
+    ("magma", &["SUNDIALS_SUNLINSOL_MAGMADENSE", "SUNDIALS_MAGMA_ENABLED"]),
+    ("onemkl", &["SUNDIALS_SUNLINSOL_ONEMKLDENSE", "SUNDIALS_ONEMKL_ENABLED"]),
+    ("ginkgo", &["SUNDIALS_GINKGO_ENABLED"]),
+    ("kokkos", &["SUNDIALS_NVECTOR_KOKKOS", "SUNDIALS_KOKKOS_ENABLED"]),
+    ("petsc", &["SUNDIALS_NVECTOR_PETSC", "SUNDIALS_PETSC_ENABLED"]),
+    ("hypre", &["SUNDIALS_NVECTOR_PARHYP", "SUNDIALS_HYPRE_ENABLED"]),
+    ("trilinos", &["SUNDIALS_NVECTOR_TRILINOS", "SUNDIALS_TRILINOS_ENABLED"]),
+    ("cuda", &["SUNDIALS_NVECTOR_CUDA"]),
+    ("hip", &["SUNDIALS_NVECTOR_HIP"]),
+    ("sycl", &["SUNDIALS_NVECTOR_SYCL"]),
+    ("raja", &["SUNDIALS_NVECTOR_RAJA", "SUNDIALS_RAJA_ENABLED"]),
+    ("monitoring", &["SUNDIALS_BUILD_WITH_MONITORING"]),
+    ("profiling", &["SUNDIALS_BUILD_WITH_PROFILING"]),
+    ("caliper", &["SUNDIALS_CALIPER_ENABLED"]),
+];
+
+/// Features and the capability (see [`CAPABILITIES`]) they need.
+const FEATURE_CAPABILITIES: &[(&str, &str)] = &[
+    ("klu", "klu"), ("lapack", "lapack"), ("superlumt", "superlumt"),
+    ("superludist", "superludist"), ("magma", "magma"), ("onemkl", "onemkl"),
+    ("ginkgo", "ginkgo"), ("kokkos", "kokkos"), ("petsc", "petsc"),
+    ("hypre", "hypre"), ("trilinos", "trilinos"), ("nveccuda", "cuda"),
+    ("nvechip", "hip"), ("nvecsycl", "sycl"), ("nvecraja", "raja"),
+    ("nvecparallel", "mpi"), ("nvecopenmp", "openmp"),
+    ("nvecpthreads", "pthreads"),
+];
+
+/// Capabilities (see [`CAPABILITIES`]) requested by the features: a
+/// system SUNDIALS lacking one of them is not used.
+fn requested_capabilities() -> Vec<&'static str> {
+    let mut capabilities = vec![];
+    if cfg!(feature = "monitoring") {
+        capabilities.push("monitoring");
+    }
+    if cfg!(feature = "profiling") {
+        capabilities.push("profiling");
+    }
+    if cfg!(feature = "caliper") {
+        capabilities.push("caliper");
+    }
+    capabilities
+}
+
+/// Capabilities (see [`CAPABILITIES`]) of SUNDIALS according to its
+/// configuration `defines`.  Defines set to `0` (`#cmakedefine01`)
+/// do not count.
+fn get_capabilities(defines: &HashMap<String, String>) -> Vec<&'static str> {
+    CAPABILITIES.iter()
+        .filter(|(_, names)| names.iter().any(|n| {
+            defines.get(*n).is_some_and(|v| v.trim() != "0")
+        }))
+        .map(|(c, _)| *c)
+        .collect()
+}
+
+/// Whether the environment variable `var` requests static
+/// (`Some(true)`) or dynamic (`Some(false)`) linking.
+fn env_static(var: &str) -> Option<bool> {
+    match env::var(var).ok()?.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Features whose libraries are named `sundials_*<part>*` (when it
+/// is not the feature itself, as for the vectors and integrators).
+const LIBRARY_FEATURES: &[(&str, &str)] = &[
+    ("klu", "klu"), ("lapack", "lapack"), ("superlumt", "superlumt"),
+    ("slunrloc", "superludist"), ("superludist", "superludist"),
+    ("magma", "magma"), ("onemkl", "onemkl"), ("cusparse", "cusparse"),
+    ("cusolversp", "cusolversp"), ("petsc", "petsc"), ("parhyp", "hypre"),
+    ("trilinos", "trilinos"), ("xbraid", "xbraid"), ("raja", "nvecraja"),
+];
+
+/// Fail with a readable message if some of the libraries `lib_names`
+/// (`sundials_<name>`, given to the linker as `link_names`) are not
+/// in `dir`, instead of letting the link fail later.
+fn check_libraries(dir: &str, lib_names: &[&str], link_names: &[String]) {
+    let exists = |name: &str| {
+        ["lib{}.a", "lib{}.so", "lib{}.dylib", "lib{}.dll.a", "{}.lib"].iter()
+            .any(|f| Path::new(dir).join(f.replace("{}", name)).is_file())
+    };
+    let errors: Vec<String> = lib_names.iter().zip(link_names)
+        .filter(|(_, link)| !exists(link))
+        .map(|(lib, link)| {
+            let feature = LIBRARY_FEATURES.iter()
+                .find(|(part, _)| lib.contains(part)).map(|(_, f)| *f)
+                .or_else(|| {
+                    let always = ["core", "nvecserial"].contains(lib)
+                        || lib.starts_with("sun");
+                    (!always).then_some(*lib)
+                });
+            match feature {
+                Some(f) => format!("feature `{}` requested but lib{} was not \
+                                    found in {} (SUNDIALS built without it?)",
+                                   f, link, dir),
+                None => format!("lib{} was not found in {}", link, dir),
+            }
+        })
+        .collect();
+    if !errors.is_empty() {
+        panic!("{}.  Set SUNDIALS_LIBRARY_DIR to a complete SUNDIALS \
+                install or enable the feature `build_libraries`.",
+               errors.join(";\n"));
     }
+}
 
-    let static_libraries = feature!("static_libraries");
-    let (shared_libraries, library_type) = match static_libraries {
-        "ON" => ("OFF", "static"),
-        "OFF" => ("ON", "dylib"),
-        _ => unreachable!(),
+/// Kind and name to pass to the linker for the library
+/// `sundials_<name>` of type `library_type` ("static" or "dylib").
+/// File names differ between Unix, MSVC (where static libraries get
+/// a `_static` suffix) and MinGW, so the ones present in `dir` are
+/// probed (preferring `library_type`).  If `dir` is unknown, the
+/// usual name for the target is assumed.
+fn link_name(
+    dir: Option<&str>, name: &str, library_type: &'static str
+) -> (&'static str, String) {
+    let base = format!("sundials_{}", name);
+    let stat = format!("{}_static", base);
+    let msvc = env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|e| e == "msvc");
+    let Some(dir) = dir else {
+        return if library_type == "static" && msvc {
+            ("static", stat)
+        } else {
+            (library_type, base)
+        }
+    };
+    let static_files = [
+        (format!("lib{}.a", base), &base),
+        (format!("{}.lib", stat), &stat),
+        (format!("lib{}.a", stat), &stat),
+        (format!("{}.lib", base), &base),
+    ];
+    let dylib_files = [
+        (format!("lib{}.so", base), &base),
+        (format!("lib{}.dylib", base), &base),
+        (format!("lib{}.dll.a", base), &base), // MinGW import library
+        (format!("{}.lib", base), &base), // MSVC import library
+    ];
+    let find = |files: &[(String, &String)]| {
+        files.iter().find(|(f, _)| Path::new(dir).join(f).is_file())
+            .map(|(_, n)| n.to_string())
+    };
+    let (first, second) = if library_type == "static" {
+        (("static", find(&static_files)), ("dylib", find(&dylib_files)))
+    } else {
+        (("dylib", find(&dylib_files)), ("static", find(&static_files)))
     };
+    match (first, second) {
+        ((kind, Some(n)), _) => (kind, n),
+        (_, (kind, Some(n))) => {
+            println!("cargo:warning=No {} library for {} in {}, \
+                      using the {} one.", library_type, base, dir, kind);
+            (kind, n)
+        }
+        _ => (library_type, base),
+    }
+}
 
-    let mut config = cmake::Config::new("vendor");
-    config
-        .define("CMAKE_INSTALL_BINDIR", "lib") // v7.1.0
-        .define("CMAKE_INSTALL_LIBDIR", "lib") // ≤ v7.0.0
-        .define("BUILD_STATIC_LIBS", static_libraries)
-        .define("BUILD_SHARED_LIBS", shared_libraries)
-        .define("BUILD_TESTING", "OFF")
-        .define("EXAMPLES_INSTALL", "OFF")
-        .define("EXAMPLES_ENABLE_C", "OFF")
-        .define("BUILD_ARKODE", feature!("arkode"))
-        .define("BUILD_CVODE", feature!("cvode"))
-        .define("BUILD_CVODES", feature!("cvodes"))
-        .define("BUILD_IDA", feature!("ida"))
-        .define("BUILD_IDAS", feature!("idas"))
-        .define("BUILD_KINSOL", feature!("kinsol"))
-		.define("ENABLE_KLU", feature!("klu"))
-        .define("OPENMP_ENABLE", feature!("nvecopenmp"))
-        .define("PTHREAD_ENABLE", feature!("nvecpthreads"));
-    if let Some(inc) = &klu.inc {
-        config.define("KLU_INCLUDE_DIR", inc);
+/// Copy the directory `from` into `to` (recursively, overwriting the
+/// existing files).
+fn copy_dir(from: &Path, to: &Path) {
+    let _ = fs::create_dir_all(to);
+    for entry in fs::read_dir(from).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()));
+        } else if let Err(e) = fs::copy(&path, to.join(entry.file_name())) {
+            panic!("Cannot copy {}: {}", path.display(), e);
+        }
     }
-    if let Some(lib) = &klu.lib {
-        config.define("KLU_LIBRARY_DIR", lib);
+}
+
+/// Copy the SUNDIALS DLLs, found in `dirs` or in the `bin` directory
+/// next to them, to the target profile directory (and its `deps`
+/// subdirectory, where tests are) so that Windows finds them when
+/// running the executables.
+fn copy_dlls(dirs: &[PathBuf]) {
+    // OUT_DIR = <target>/<profile>/build/sundials-sys-<hash>/out
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let Some(profile) = out_dir.ancestors().nth(3) else { return };
+    let dirs = dirs.iter()
+        .flat_map(|d| [d.clone(), d.parent().unwrap_or(d).join("bin")]);
+    for dll in dirs.filter_map(|d| fs::read_dir(d).ok()).flatten().flatten() {
+        let name = dll.file_name().to_string_lossy().to_string();
+        if !(name.starts_with("sundials_") && name.ends_with(".dll")) {
+            continue
+        }
+        for dest in [profile.to_path_buf(), profile.join("deps")] {
+            if let Err(e) = fs::copy(dll.path(), dest.join(&name)) {
+                println!("cargo:warning=Cannot copy {} to {}: {}",
+                         name, dest.display(), e);
+            }
+        }
     }
+}
 
-    let dst = config.build();
-    let dst_disp = dst.display();
-    let lib_loc = if cfg!(feature = "static_libraries") {
-        Some(format!("{}/build/bin/Debug", dst_disp))
+/// Merge the static libraries `names` (as given to the linker) found
+/// in `dir` into a single archive `sundials_bundle` in `out_dir`.
+fn bundle_static_libraries(
+    dir: &str, names: &[String], out_dir: &Path
+) -> Result<(), String> {
+    let target = env::var("TARGET").unwrap_or_default();
+    let msvc = target.contains("msvc");
+    let archive = |name: &str| if msvc {
+        format!("{}.lib", name)
     } else {
-        Some(format!("{}/lib", dst_disp))
+        format!("lib{}.a", name)
     };
-    let inc_dir = Some(format!("{}/include", dst_disp));
-    (Library { inc: inc_dir, lib: lib_loc }, library_type)
-}
-
-fn generate_bindings(inc_dirs: &[Option<String>]) -> Result<Bindings, BindgenError>
-{
-    macro_rules! define {
-        ($a:tt, $b:tt) => {
-            format!(
-                "-DUSE_{}={}",
-                stringify!($b),
-                if cfg!(feature = $a) { 1 } else { 0 }
-            )
-        };
+    let mut libs = vec![];
+    for name in names {
+        let lib = Path::new(dir).join(archive(name));
+        if !lib.is_file() {
+            return Err(format!("{} not found", lib.display()))
+        }
+        libs.push(lib);
     }
-
-    let mut builder = bindgen::Builder::default().header("wrapper.h");
-    for dir in inc_dirs {
-        if let Some(dir) = dir {
-            builder = builder.clang_arg(format!("-I{}", dir))
+    let bundle = out_dir.join(archive("sundials_bundle"));
+    let _ = fs::remove_file(&bundle);
+    let mut cmd = cc::Build::new().cargo_metadata(false).get_archiver();
+    let status = if msvc {
+        cmd.arg(format!("/OUT:{}", bundle.display())).args(&libs).status()
+    } else if target.contains("apple") {
+        std::process::Command::new("libtool")
+            .arg("-static").arg("-o").arg(&bundle).args(&libs).status()
+    } else {
+        // GNU ar MRI script.
+        use std::{io::Write, process::Stdio};
+        let mut script = format!("CREATE {}\n", bundle.display());
+        for lib in &libs {
+            script.push_str(&format!("ADDLIB {}\n", lib.display()));
         }
+        script.push_str("SAVE\nEND\n");
+        cmd.arg("-M").stdin(Stdio::piped()).spawn().and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(script.as_bytes())?;
+            child.wait()
+        })
+    };
+    match status {
+        Ok(s) if s.success() => Ok(()),
+        Ok(s) => Err(format!("archiver failed ({})", s)),
+        Err(e) => Err(e.to_string()),
     }
-    builder
-        .clang_args(&[
-            define!("arkode", ARKODE),
-            define!("cvode", CVODE),
-            define!("cvodes", CVODES),
-            define!("ida", IDA),
-            define!("idas", IDAS),
-            define!("kinsol", KINSOL),
-            define!("klu", KLU),
-            define!("nvecopenmp", OPENMP),
-            define!("nvecpthreads", PTHREADS),
-        ])
-        .parse_callbacks(Box::new(ParseSignedConstants))
-        .parse_callbacks(Box::new(IgnoreMacros::new()))
-        .generate()
-}
-
-fn get_sundials_version_major(bindings: impl AsRef<Path>) -> Option<u32> {
-    let b = File::open(bindings).expect("Couldn't read file bindings.rs!");
-    let mut b = BufReader::new(b).bytes();
-    'version:
-    while b.find(|c| c.as_ref().is_ok_and(|&c| c == b'S')).is_some() {
-        for c0 in "UNDIALS_VERSION_MAJOR".bytes() {
-            match b.next() {
-                Some(Ok(c)) => {
-                    if c != c0 {
-                        continue 'version
-                    }
+}
+
+/// Names of the SUNDIALS libraries to link (without the `sundials_`
+/// prefix) for the enabled features and the SUNDIALS `major` version.
+fn library_names(major: u32) -> Vec<String> {
+    let mut lib_names = vec![];
+    if major >= 7 {
+        lib_names.push("core");
+    }
+    lib_names.push("nvecserial");
+    if solvers_enabled() {
+        lib_names.extend([
+            "sunlinsolband",
+            "sunlinsoldense",
+            "sunlinsolpcg",
+            "sunlinsolspbcgs",
+            "sunlinsolspfgmr",
+            "sunlinsolspgmr",
+            "sunlinsolsptfqmr",
+            "sunmatrixband",
+            "sunmatrixdense",
+            "sunmatrixsparse",
+            "sunnonlinsolfixedpoint",
+            "sunnonlinsolnewton",
+        ]);
+    }
+    if cfg!(feature = "klu") {
+        lib_names.push("sunlinsolklu");
+    }
+    if cfg!(feature = "lapack") {
+        lib_names.extend(["sunlinsollapackdense", "sunlinsollapackband"]);
+    }
+    if cfg!(feature = "superlumt") {
+        lib_names.push("sunlinsolsuperlumt");
+    }
+    if cfg!(feature = "superludist") {
+        lib_names.extend(["sunmatrixslunrloc", "sunlinsolsuperludist"]);
+    }
+    if cfg!(feature = "magma") {
+        lib_names.extend(["sunmatrixmagmadense", "sunlinsolmagmadense"]);
+    }
+    if cfg!(feature = "onemkl") {
+        lib_names.extend(["sunmatrixonemkldense", "sunlinsolonemkldense"]);
+    }
+    if cfg!(feature = "cusparse") {
+        lib_names.push("sunmatrixcusparse");
+    }
+    if cfg!(feature = "cusolversp") {
+        lib_names.push("sunlinsolcusolversp");
+    }
+    macro_rules! link { ($($s:tt),*) => {
+        $(if cfg!(feature = $s) { lib_names.push($s) })*
+    }}
+    if cfg!(feature = "petsc") {
+        lib_names.extend(["nvecpetsc", "sunnonlinsolpetscsnes"]);
+    }
+    if cfg!(feature = "hypre") {
+        lib_names.push("nvecparhyp");
+    }
+    if cfg!(feature = "trilinos") {
+        lib_names.push("nvectrilinos");
+    }
+    if cfg!(feature = "xbraid") {
+        lib_names.push("arkode_xbraid");
+    }
+    link! ("arkode", "cvode", "cvodes", "ida", "idas", "kinsol",
+        "nvecopenmp", "nvecopenmpdev", "nvecpthreads", "nvecparallel",
+        "nveccuda", "nvechip", "nvecsycl", "nvecmanyvector",
+        "nvecmpimanyvector", "nvecmpiplusx");
+    let raja;
+    if cfg!(feature = "nvecraja") {
+        raja = format!("nvec{}raja", raja_backend());
+        lib_names.push(&raja);
+    }
+    // The integrator libraries already contain the native matrices,
+    // linear and nonlinear solvers (for their default modules), so
+    // only the ones a program uses may be linked.
+    if let Ok(modules) = env::var("SUNDIALS_SYS_LINK_MODULES") {
+        let modules: Vec<&str> = modules.split(',').map(str::trim).collect();
+        lib_names.retain(|name| {
+            !["sunmatrix", "sunlinsol", "sunnonlinsol"].iter()
+                .any(|p| name.starts_with(p))
+                || modules.contains(name)
+        });
+    }
+    lib_names.into_iter().map(String::from).collect()
+}
+
+/// The cargo directives to link the SUNDIALS libraries.
+fn link_directives(
+    sundials: &Library, library_type: &'static str, version: Version
+) -> Vec<String> {
+    let mut directives = vec![];
+    if let Some(dir) = &sundials.lib {
+        directives.push(format!("rustc-link-search=native={}", dir));
+    }
+
+    let lib_names = library_names(version.major);
+    let lib_names: Vec<&str> = lib_names.iter().map(|n| n.as_str()).collect();
+
+    let links: Vec<(&str, String)> = lib_names.iter()
+        .map(|lib_name| {
+            // Per library override, e.g. `SUNDIALS_NVECSERIAL_STATIC=0`.
+            let var = format!("SUNDIALS_{}_STATIC", lib_name.to_uppercase());
+            let kind = match env_static(&var) {
+                Some(true) => "static",
+                Some(false) => "dylib",
+                None => library_type,
+            };
+            link_name(sundials.lib.as_deref(), lib_name, kind)
+        })
+        .collect();
+    let link_names: Vec<String> = links.iter().map(|(_, n)| n.clone()).collect();
+    if let Some(dir) = &sundials.lib {
+        check_libraries(dir, &lib_names, &link_names);
+    }
+
+    // Static SUNDIALS libraries use the math library (pow, exp,...)
+    // which is not always linked by default.  It is part of the C
+    // runtime on MSVC, wasm and musl (whose self-contained Rust
+    // toolchain has no libm.a).
+    let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+    let target_family = env::var("CARGO_CFG_TARGET_FAMILY").unwrap_or_default();
+    let needs_libm = links.iter().any(|(kind, _)| *kind == "static")
+        && target_env != "msvc" && target_env != "musl"
+        && !target_family.split(',').any(|f| f == "wasm");
+
+    let mut bundled = false;
+    if cfg!(feature = "static_bundle") && library_type == "static" {
+        if let Some(dir) = &sundials.lib {
+            let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+            match bundle_static_libraries(dir, &link_names, &out_dir) {
+                Ok(()) => {
+                    directives.push(format!("rustc-link-search=native={}",
+                                            out_dir.display()));
+                    directives.push("rustc-link-lib=static=sundials_bundle".into());
+                    bundled = true;
                 }
-                Some(Err(_)) | None => return None
+                Err(e) => println!("cargo:warning=Could not bundle the \
+                    static libraries ({}), linking them separately.", e),
             }
         }
-        // Match " : u32 = 6"
-        if b.find(|c| c.as_ref().is_ok_and(|&c| c == b'=')).is_some() {
-            let is_not_digit = |c: &u8| !c.is_ascii_digit();
-            let b = b.skip_while(|c| c.as_ref().is_ok_and(is_not_digit));
-            let v: Vec<_> =
-                b.map_while(|c| c.ok().filter(|c| c.is_ascii_digit()))
-                .collect();
-            match String::from_utf8(v) {
-                Ok(v) => return v.parse().ok(),
-                Err(_) => return None
+    }
+    if !bundled {
+        for (kind, link_name) in &links {
+            directives.push(format!("rustc-link-lib={}={}", kind, link_name));
+        }
+    }
+    if cfg!(all(feature = "klu", feature = "static_all")) {
+        directives.extend(klu_static_directives());
+    }
+    if cfg!(feature = "superlumt") && library_type == "static" {
+        directives.extend(superlumt_directives());
+    }
+    if cfg!(feature = "superludist") && library_type == "static" {
+        directives.extend(superludist_directives());
+    }
+    if cfg!(feature = "magma") && library_type == "static" {
+        if let Some(dir) = magma_library().lib {
+            directives.push(format!("rustc-link-search=native={}", dir));
+        }
+        directives.push("rustc-link-lib=magma".into());
+        let blas: &[_] = match magma_backend() {
+            "cuda" => &["cublas", "cusparse"],
+            _ => &["hipblas", "hipsparse"],
+        };
+        for lib in blas {
+            directives.push(format!("rustc-link-lib=dylib={}", lib));
+        }
+    }
+    if cfg!(feature = "onemkl") && library_type == "static" {
+        if let Some(dir) = onemkl_library().lib {
+            directives.push(format!("rustc-link-search=native={}", dir));
+        }
+        // The integer interface must match the size of `sunindextype`.
+        let interface = if requested_index_size() == Some(32) {
+            "mkl_intel_lp64"
+        } else {
+            "mkl_intel_ilp64"
+        };
+        for lib in ["mkl_sycl", interface, "mkl_sequential", "mkl_core"] {
+            directives.push(format!("rustc-link-lib=dylib={}", lib));
+        }
+    }
+    if cfg!(feature = "lapack") && library_type == "static" {
+        directives.extend(lapack_directives());
+    }
+    if needs_libm {
+        directives.push("rustc-link-lib=m".into());
+    }
+    if cfg!(feature = "nveccuda") {
+        directives.push(format!("rustc-link-search=native={}",
+                                cuda_lib_dir().display()));
+        directives.push("rustc-link-lib=dylib=cudart".into());
+        // The static CUDA vector is compiled C++.
+        if library_type == "static" && target_env != "msvc" {
+            directives.push("rustc-link-lib=dylib=stdc++".into());
+        }
+    }
+    if cfg!(feature = "petsc") {
+        // The PETSc vectors and SNES are created by the user.
+        if let Some(dir) = petsc_library().0.lib {
+            directives.push(format!("rustc-link-search=native={}", dir));
+        }
+        directives.push("rustc-link-lib=petsc".into());
+    }
+    if cfg!(feature = "hypre") {
+        // The hypre vectors are created by the user.
+        if let Some(dir) = hypre_library().lib {
+            directives.push(format!("rustc-link-search=native={}", dir));
+        }
+        directives.push("rustc-link-lib=HYPRE".into());
+    }
+    if cfg!(feature = "trilinos") && library_type == "static" {
+        if let Some(dir) = trilinos_library().lib {
+            directives.push(format!("rustc-link-search=native={}", dir));
+        }
+        for lib in ["tpetra", "teuchoscomm", "teuchoscore", "kokkoscore"] {
+            directives.push(format!("rustc-link-lib={}", lib));
+        }
+        if target_env != "msvc" {
+            directives.push("rustc-link-lib=dylib=stdc++".into());
+        }
+    }
+    if cfg!(feature = "xbraid") {
+        // XBraid is only built as a static library; its core is
+        // driven by the user.
+        if let Some(dir) = xbraid_library().lib {
+            directives.push(format!("rustc-link-search=native={}", dir));
+        }
+        directives.push("rustc-link-lib=static=braid".into());
+    }
+    if cfg!(feature = "caliper") && library_type == "static" {
+        if let Some(dir) = caliper_library().lib {
+            directives.push(format!("rustc-link-search=native={}", dir));
+        }
+        directives.push("rustc-link-lib=caliper".into());
+        if env::var("adiak_DIR").is_ok() {
+            directives.push("rustc-link-lib=adiak".into());
+        }
+    }
+    // The handles are created by the user.
+    if cfg!(feature = "cusparse") {
+        directives.push("rustc-link-lib=dylib=cusparse".into());
+    }
+    if cfg!(feature = "cusolversp") {
+        directives.push("rustc-link-lib=dylib=cusolver".into());
+    }
+    if cfg!(feature = "nvechip") {
+        directives.push(format!("rustc-link-search=native={}",
+                                rocm_home().join("lib").display()));
+        directives.push("rustc-link-lib=dylib=amdhip64".into());
+        if library_type == "static" {
+            directives.push("rustc-link-lib=dylib=stdc++".into());
+        }
+    }
+    if cfg!(feature = "nvecsycl") {
+        directives.push(format!("rustc-link-search=native={}",
+                                sycl_home().join("lib").display()));
+        directives.push("rustc-link-lib=dylib=sycl".into());
+        if library_type == "static" {
+            directives.push("rustc-link-lib=dylib=stdc++".into());
+        }
+    }
+    if cfg!(feature = "nvecraja") && library_type == "static" {
+        if let Ok(dir) = env::var("RAJA_DIR") {
+            directives.push(format!("rustc-link-search=native={}/lib", dir));
+        }
+        directives.push("rustc-link-lib=RAJA".into());
+    }
+    if cfg!(feature = "nvtx") {
+        directives.extend(nvtx_directives());
+    }
+    directives
+}
+
+/// Link directives for the NVTX library of the CUDA toolkit.
+fn nvtx_directives() -> Vec<String> {
+    let windows = env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "windows");
+    let lib = if windows { "nvToolsExt64_1" } else { "nvToolsExt" };
+    vec![format!("rustc-link-search=native={}", cuda_lib_dir().display()),
+         format!("rustc-link-lib=dylib={}", lib)]
+}
+
+/// Description of the build configuration, for other tools.
+struct BuildConfig<'a> {
+    /// Where SUNDIALS comes from: "env" (`SUNDIALS_*_DIR`), "conda",
+    /// "pkg-config", "system", "vcpkg" or "vendor".
+    source: &'a str,
+    version: Version,
+    precision: &'a str,
+    index_size: u32,
+    include_dirs: Vec<&'a String>,
+    lib_dir: Option<&'a String>,
+    library_type: &'a str,
+    components: Vec<&'a str>,
+    /// Capabilities of the library (see [`CAPABILITIES`]).
+    capabilities: Vec<&'a str>,
+    /// Cargo link directives.
+    link: Vec<String>,
+}
+
+impl BuildConfig<'_> {
+    fn to_json(&self) -> String {
+        fn string(s: &str) -> String {
+            let mut j = String::from("\"");
+            for c in s.chars() {
+                match c {
+                    '"' => j.push_str("\\\""),
+                    '\\' => j.push_str("\\\\"),
+                    c if c.is_control() => j.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => j.push(c),
+                }
             }
+            j.push('"');
+            j
         }
-        return None
+        fn array<S: AsRef<str>>(v: &[S]) -> String {
+            let v: Vec<_> = v.iter().map(|s| string(s.as_ref())).collect();
+            format!("[{}]", v.join(", "))
+        }
+        let v = self.version;
+        format!("{{\n  \"source\": {},\n  \"version\": {},\n  \
+                 \"version_major\": {},\n  \"version_minor\": {},\n  \
+                 \"version_patch\": {},\n  \"precision\": {},\n  \
+                 \"index_size\": {},\n  \"include_dirs\": {},\n  \
+                 \"lib_dir\": {},\n  \"library_type\": {},\n  \
+                 \"components\": {},\n  \"capabilities\": {},\n  \
+                 \"link\": {}\n}}\n",
+                string(self.source),
+                string(&format!("{}.{}.{}", v.major, v.minor, v.patch)),
+                v.major, v.minor, v.patch,
+                string(self.precision), self.index_size,
+                array(&self.include_dirs),
+                self.lib_dir.map_or("null".into(), |d| string(d)),
+                string(self.library_type),
+                array(&self.components), array(&self.capabilities),
+                array(&self.link))
     }
-    None
 }
 
 fn main() {
+    if verbose_enabled() {
+        let mut vars: Vec<(String, String)> = env::vars()
+            .filter(|(name, _)| name.starts_with("SUNDIALS_")
+                    || READ_VARS.contains(&name.as_str()))
+            .collect();
+        vars.sort();
+        for (name, value) in vars {
+            verbose("env", format!("{}={}", name, value));
+        }
+    }
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let native = env::var("HOST").ok() == env::var("TARGET").ok();
+    let macos = target_os == "macos" && native;
+
     // get klu dirs
-    let klu_inc = env::var("DEP_SUITESPARSE_SUITESPARSE_INCLUDE").ok();
-    let klu_lib = env::var("DEP_SUITESPARSE_SUITESPARSE_LIB").ok();
+    let mut klu_inc = env::var("DEP_SUITESPARSE_SUITESPARSE_INCLUDE").ok();
+    let mut klu_lib = env::var("DEP_SUITESPARSE_SUITESPARSE_LIB").ok();
+    if cfg!(feature = "klu") && klu_inc.is_none() {
+        let mut prefixes = conda_prefixes();
+        if macos {
+            prefixes.extend(macos_prefixes("suite-sparse"));
+        }
+        if let Some(lib) = probe_klu(&prefixes) {
+            (klu_inc, klu_lib) = (lib.inc, lib.lib);
+        }
+    }
+    if cfg!(feature = "klu") {
+        verbose("klu", Library { inc: klu_inc.clone(), lib: klu_lib.clone() });
+    }
 
     // First, we build the SUNDIALS library, with requested modules with CMake
-    let klu = Library { inc: klu_inc, lib: klu_lib };
+    let (petsc, petsc_inc) = petsc_library();
+    let deps = Deps {
+        klu: Library { inc: klu_inc, lib: klu_lib },
+        superlumt: superlumt_library(),
+        superludist: superludist_library(),
+        magma: magma_library(),
+        onemkl: onemkl_library(),
+        petsc,
+        hypre: hypre_library(),
+        trilinos: trilinos_library(),
+        xbraid: xbraid_library(),
+        caliper: caliper_library(),
+        extra_inc: petsc_inc,
+    };
     let mut sundials = Library { inc: None, lib: None };
     let mut library_type = "dylib";
-    if cfg!(any(feature = "build_libraries", target_family = "wasm")) {
-        (sundials, library_type) = build_vendor_sundials(&klu);
+    let mut source = "system";
+    if cfg!(feature = "build_libraries") || is_wasm() || is_mobile() {
+        verbose("vendor", "required (feature build_libraries, wasm or \
+                           mobile target)");
+        (sundials, library_type, source) = build_vendor_sundials(&deps);
     } else {
         sundials.inc = env::var("SUNDIALS_INCLUDE_DIR").ok();
         sundials.lib = env::var("SUNDIALS_LIBRARY_DIR").ok();
+        if sundials.inc.is_some() || sundials.lib.is_some() {
+            source = "env";
+            verbose("env", &sundials);
+        }
+    }
+
+    if sundials.lib.is_none() && sundials.inc.is_none() {
+        // conda-forge packages, in the active environment.
+        let prefixes = conda_prefixes();
+        let prefixes: Vec<&str> = prefixes.iter().map(|p| p.as_str()).collect();
+        if let Some(lib) = probe_prefixes(&prefixes) {
+            sundials = lib;
+            source = "conda";
+        }
+        if !prefixes.is_empty() {
+            verbose("conda", format!("{:?}: {}", prefixes, sundials));
+        }
+    }
+
+    if sundials.lib.is_none() && sundials.inc.is_none() {
+        // Distributions and Homebrew install `.pc` files.
+        if let Some(lib) = pkg_config_sundials() {
+            sundials = lib;
+            source = "pkg-config";
+        }
+        if cfg!(feature = "pkg-config") {
+            verbose("pkg-config", &sundials);
+        }
+    }
+
+    if sundials.lib.is_none() && sundials.inc.is_none() {
+        let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+        if macos {
+            let prefixes = macos_prefixes("sundials");
+            let prefixes: Vec<&str> = prefixes.iter().map(|p| p.as_str()).collect();
+            if let Some(lib) = probe_prefixes(&prefixes) {
+                sundials = lib;
+            }
+        } else if ["freebsd", "openbsd", "netbsd", "dragonfly"]
+            .contains(&target_os.as_str())
+        {
+            // Packages are installed in a prefix that the C compiler
+            // (and thus bindgen) does not search by default.
+            if let Some(lib) = probe_prefixes(&["/usr/local", "/usr/pkg"]) {
+                sundials = lib;
+            }
+        } else if target_env == "musl" && native
+            && Path::new("/etc/alpine-release").exists()
+        {
+            // apk installs sundials-dev in /usr (with no multiarch
+            // subdirectory); knowing the library directory allows to
+            // probe the library files that are present.
+            if let Some(lib) = probe_prefixes(&["/usr", "/usr/local"]) {
+                sundials = lib;
+            }
+        }
+        verbose("prefixes", &sundials);
+    }
+
+    if sundials.lib.is_none() && sundials.inc.is_none()
+        && env::var_os("NIX_CFLAGS_COMPILE").is_some()
+    {
+        if let Some(lib) = nix_sundials() {
+            sundials = lib;
+        }
+        verbose("nix", &sundials);
     }
 
+    #[allow(unused_mut)]
+    let mut vcpkg_dlls: Vec<PathBuf> = vec![];
     if sundials.lib.is_none() && sundials.inc.is_none() {
         #[cfg(target_family = "windows")] {
             let vcpkg = vcpkg::Config::new()
-                .emit_includes(true)
+                .cargo_metadata(false)
                 .find_package("sundials");
-            if vcpkg.is_err() {
-                (sundials, library_type) = build_vendor_sundials(&klu);
+            match vcpkg {
+                Ok(lib) => {
+                    sundials.inc = lib.include_paths.first()
+                        .map(|p| p.display().to_string());
+                    sundials.lib = lib.link_paths.first()
+                        .map(|p| p.display().to_string());
+                    // Static triplets (e.g. `x64-windows-static`,
+                    // `x64-windows-static-md`) only have static libraries.
+                    let triplet = env::var("VCPKGRS_TRIPLET").unwrap_or_default();
+                    if lib.is_static || triplet.contains("-static") {
+                        library_type = "static";
+                    }
+                    vcpkg_dlls = lib.dll_paths;
+                    source = "vcpkg";
+                    verbose("vcpkg", &sundials);
+                }
+                Err(e) => {
+                    verbose("vcpkg", e);
+                    (sundials, library_type, source) = build_vendor_sundials(&deps);
+                }
             }
         }
     }
 
+    // System libraries are linked dynamically unless requested
+    // otherwise, as most -sys crates do (or the executable is static).
+    if source != "vendor"
+        && env_static("SUNDIALS_STATIC").unwrap_or(static_musl())
+    {
+        library_type = "static";
+    }
+
     // Second, we use bindgen to generate the Rust types
 
     let bindings_rs = PathBuf::from(env::var("OUT_DIR").unwrap())
         .join("bindings.rs");
     let mut build_vendor = true;
-    let mut sundials_version_major = 0;
-    if let Ok(bindings) = generate_bindings(&[sundials.inc, klu.inc.clone()]) {
-        bindings.write_to_file(&bindings_rs)
-            .expect("Couldn't write file bindings.rs!");
-        if let Some(v) = get_sundials_version_major(&bindings_rs) {
-            if v >= 6 {
-                build_vendor = false;
-                sundials_version_major = v;
-            } else {
+    let mut version = Version { major: 0, minor: 0, patch: 0 };
+    let mut defines = HashMap::new();
+    if let Ok(files) = generate_bindings(
+        &deps.include_dirs(&sundials), &bindings_rs,
+        static_define(library_type))
+    {
+        defines = sundials_config(&files).unwrap_or_default();
+        if let Some(v) = get_sundials_version(&defines) {
+            let precision = get_precision(&defines);
+            if v.major < 6 {
                 println!("cargo:warning=System sundials version = \
-                          {} < 6, will use the vendor version", v);
+                          {}.{}.{} < 6, will use the vendor version",
+                         v.major, v.minor, v.patch);
+            } else if requested_precision()
+                .map_or(precision == "extended", |p| p != precision)
+            {
+                println!("cargo:warning=System sundials has {} precision, \
+                          will use the vendor version", precision);
+            } else if requested_index_size()
+                .is_some_and(|s| s != get_index_size(&defines))
+            {
+                println!("cargo:warning=System sundials has {} bits \
+                          indices, will use the vendor version",
+                         get_index_size(&defines));
+            } else if requested_logging_level()
+                .is_some_and(|l| l > get_logging_level(&defines))
+            {
+                println!("cargo:warning=System sundials has logging level \
+                          {}, will use the vendor version",
+                         get_logging_level(&defines));
+            } else if let Some(c) = requested_capabilities().into_iter()
+                .find(|c| !get_capabilities(&defines).contains(c))
+            {
+                println!("cargo:warning=System sundials was built without \
+                          {}, will use the vendor version", c);
+            } else {
+                build_vendor = false;
+                version = v;
             }
         }
     }
     if build_vendor {
-        (sundials, library_type) = build_vendor_sundials(&klu);
-        if let Ok(bindings) = generate_bindings(&[sundials.inc, klu.inc.clone()]) {
-            bindings
-                .write_to_file(&bindings_rs)
-                .expect("Couldn't write file bindings.rs!");
-            sundials_version_major = get_sundials_version_major(&bindings_rs)
+        verbose("vendor", format!("{} SUNDIALS not found or not suitable",
+                                  source));
+        (sundials, library_type, source) = build_vendor_sundials(&deps);
+        if let Ok(files) = generate_bindings(
+            &deps.include_dirs(&sundials), &bindings_rs,
+            static_define(library_type))
+        {
+            defines = sundials_config(&files).unwrap_or_default();
+            version = get_sundials_version(&defines)
                 .expect("Cannot determine vendor sundials version!");
         } else {
             panic!("Unable to generate bindings of the vendor sundials!");
         }
     }
+    let sundials_version_major = version.major;
     println!("cargo::rustc-check-cfg=cfg(sundials_version_major, \
         values(\"6\", \"7\"))");
     println!("cargo:rustc-cfg=sundials_version_major=\"{}\"",
         sundials_version_major);
+    println!("cargo::rustc-check-cfg=cfg(sundials_version_minor, \
+        values(any()))");
+    println!("cargo:rustc-cfg=sundials_version_minor=\"{}\"", version.minor);
+    println!("cargo::rustc-check-cfg=cfg(sundials_version_patch, \
+        values(any()))");
+    println!("cargo:rustc-cfg=sundials_version_patch=\"{}\"", version.patch);
+    // `sundials_version_at_least = "x.y"` for all the releases x.y up
+    // to this one, to gate APIs added in a minor release.
+    println!("cargo::rustc-check-cfg=cfg(sundials_version_at_least, \
+        values(any()))");
+    for major in 6..=version.major {
+        let last_minor = match major {
+            _ if major == version.major => version.minor,
+            6 => 7,
+            _ => 0,
+        };
+        for minor in 0..=last_minor {
+            println!("cargo:rustc-cfg=sundials_version_at_least=\"{}.{}\"",
+                     major, minor);
+        }
+    }
+    let precision = get_precision(&defines);
+    println!("cargo::rustc-check-cfg=cfg(sundials_precision, \
+        values(\"single\", \"double\", \"extended\"))");
+    println!("cargo:rustc-cfg=sundials_precision=\"{}\"", precision);
+    // Available to dependent crates as `DEP_SUNDIALS_PRECISION`.
+    println!("cargo:precision={}", precision);
+    println!("cargo::rustc-check-cfg=cfg(sundials_long_double)");
+    if precision == "extended" && long_double_is_wide() {
+        println!("cargo:rustc-cfg=sundials_long_double");
+    }
+    let index_size = get_index_size(&defines);
+    println!("cargo::rustc-check-cfg=cfg(sundials_index_size, \
+        values(\"32\", \"64\"))");
+    println!("cargo:rustc-cfg=sundials_index_size=\"{}\"", index_size);
+    // Available to dependent crates as `DEP_SUNDIALS_INDEX_SIZE`.
+    println!("cargo:index_size={}", index_size);
+    let logging_level = get_logging_level(&defines);
+    println!("cargo::rustc-check-cfg=cfg(sundials_logging_level, \
+        values(\"0\", \"1\", \"2\", \"3\", \"4\", \"5\"))");
+    println!("cargo:rustc-cfg=sundials_logging_level=\"{}\"", logging_level);
+    let capabilities = get_capabilities(&defines);
+    for (c, _) in CAPABILITIES {
+        println!("cargo::rustc-check-cfg=cfg(sundials_has_{})", c);
+    }
+    for c in &capabilities {
+        println!("cargo:rustc-cfg=sundials_has_{}", c);
+    }
+    if source != "vendor" && !defines.is_empty() {
+        for (feature, c) in FEATURE_CAPABILITIES {
+            let enabled = env::var_os(
+                format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_some();
+            if enabled && !capabilities.contains(c) {
+                panic!("Feature `{}` requested but the sundials_config.h \
+                        of the SUNDIALS used ({}) does not announce {} \
+                        support.", feature, sundials, c);
+            }
+        }
+    }
+    // Available to dependent crates as `DEP_SUNDIALS_CAPABILITIES`.
+    println!("cargo:capabilities={}", capabilities.join(","));
+    for f in OPTIONAL_FUNCTIONS {
+        println!("cargo::rustc-check-cfg=cfg(sundials_has_fn_{})", f);
+    }
+    for f in available_functions(&bindings_rs, sundials.lib.as_deref()) {
+        println!("cargo:rustc-cfg=sundials_has_fn_{}", f);
+    }
+    if cfg!(feature = "rsmpi") && !capabilities.contains(&"mpi") {
+        println!("cargo:warning=SUNDIALS was built without MPI, \
+                  comm_from_rsmpi is not available.");
+    }
+    if capabilities.contains(&"mpi") {
+        build_mpi_comm(&sundials, static_define(library_type));
+    }
 
     // Third, we let Cargo know about the library files
 
-    if let Some(dir) = sundials.lib {
-        println!("cargo:rustc-link-search=native={}", dir)
+    let mut directives = vec![];
+    if !cfg!(any(feature = "dlopen", feature = "headers_only")) {
+        // Otherwise, libraries are loaded at runtime by `Sundials::new`
+        // or not at all.
+        directives = link_directives(&sundials, library_type, version);
+        // conda libraries are versioned (`libsundials_cvode.so.7`) and
+        // not in the loader path unless the environment sets it.
+        let unix = env::var("CARGO_CFG_TARGET_FAMILY")
+            .is_ok_and(|f| f.split(',').any(|f| f == "unix"));
+        if source == "conda" && unix {
+            directives.extend(sundials.lib.iter()
+                .map(|lib| format!("rustc-link-arg=-Wl,-rpath,{}", lib)));
+        }
     }
-
-    let mut lib_names = vec![];
-    if sundials_version_major >= 7 {
-        lib_names.push("core");
+    verbose("result", format!("{} SUNDIALS {}.{}.{} ({}), {}", source,
+                              version.major, version.minor, version.patch,
+                              library_type, sundials));
+    for d in &directives {
+        println!("cargo:{}", d);
+        verbose("link", d);
     }
-    lib_names.append(&mut vec![
-        "nvecserial",
-        "sunlinsolband",
-        "sunlinsoldense",
-        "sunlinsolpcg",
-        "sunlinsolspbcgs",
-        "sunlinsolspfgmr",
-        "sunlinsolspgmr",
-        "sunlinsolsptfqmr",
-        "sunmatrixband",
-        "sunmatrixdense",
-        "sunmatrixsparse",
-        "sunnonlinsolfixedpoint",
-        "sunnonlinsolnewton",
-    ]);
-    if cfg!(feature = "klu") {
-        lib_names.push("sunlinsolklu");
+    let windows = env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "windows");
+    if windows && library_type == "dylib"
+        && env::var("SUNDIALS_SYS_COPY_DLLS").is_ok_and(|v| v != "0")
+    {
+        vcpkg_dlls.extend(sundials.lib.iter().map(PathBuf::from));
+        copy_dlls(&vcpkg_dlls);
     }
-    macro_rules! link { ($($s:tt),*) => {
-        $(if cfg!(feature = $s) { lib_names.push($s) })*
-    }}
-    link! ("arkode", "cvode", "cvodes", "ida", "idas", "kinsol",
-        "nvecopenmp", "nvecpthreads");
 
-    for lib_name in &lib_names {
-        if library_type == "static" {
-            println!(
-                "cargo:rustc-link-lib={}=sundials_{}_static",
-                library_type, lib_name
-            );
-        } else {
-            println!(
-                "cargo:rustc-link-lib={}=sundials_{}",
-                library_type, lib_name
-            );
-        }
+    // Finally, describe the configuration for other tools.
+
+    let json = PathBuf::from(env::var("OUT_DIR").unwrap())
+        .join("sundials-config.json");
+    let mut components = vec![];
+    macro_rules! component { ($($s:tt),*) => {
+        $(if cfg!(feature = $s) { components.push($s) })*
+    }}
+    component!("arkode", "cvode", "cvodes", "ida", "idas", "kinsol",
+               "klu", "lapack", "superlumt", "superludist", "magma",
+               "onemkl", "nvecopenmp", "nvecopenmpdev", "nvecpthreads",
+               "nvecparallel", "nveccuda", "nvechip", "nvecsycl",
+               "nvecraja", "kokkos", "ginkgo", "cusparse", "cusolversp",
+               "petsc", "hypre", "trilinos", "xbraid", "nvecmanyvector",
+               "nvecmpimanyvector", "nvecmpiplusx");
+    let include_dirs = deps.include_dirs(&sundials);
+    let config = BuildConfig {
+        source,
+        version,
+        precision,
+        index_size,
+        include_dirs: include_dirs.iter().flatten().collect(),
+        lib_dir: sundials.lib.as_ref(),
+        library_type,
+        components,
+        capabilities,
+        link: directives,
+    };
+    fs::write(&json, config.to_json())
+        .expect("Couldn't write sundials-config.json!");
+    println!("cargo:config_json={}", json.display());
+    // `DEP_SUNDIALS_INCLUDE`, `DEP_SUNDIALS_LIB` and
+    // `DEP_SUNDIALS_VERSION` for the C code of dependent crates.
+    if let Some(inc) = &sundials.inc {
+        println!("cargo:include={}", inc);
+    }
+    if let Some(lib) = &sundials.lib {
+        println!("cargo:lib={}", lib);
     }
+    println!("cargo:version={}.{}.{}", version.major, version.minor,
+             version.patch);
     // And that's all.
 }
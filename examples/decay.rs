@@ -0,0 +1,60 @@
+//! Integrate y' = -y, y(0) = 1, up to t = 1 with CVODE and check the
+//! result.  This is a minimal program used to smoke-test the crate on
+//! targets where the test harness is not available (e.g. wasm32).
+
+#[allow(unused_imports)]
+use std::{ptr, ffi::{c_int, c_void}, process::ExitCode};
+#[allow(unused_imports)]
+use sundials_sys::*;
+
+#[cfg(not(feature = "dlopen"))]
+unsafe extern "C" fn rhs(
+    _t: realtype, y: N_Vector, ydot: N_Vector, _user_data: *mut c_void,
+) -> c_int {
+    *N_VGetArrayPointer(ydot) = - *N_VGetArrayPointer(y);
+    0
+}
+
+#[cfg(feature = "dlopen")]
+fn main() -> ExitCode {
+    eprintln!("This example links SUNDIALS, disable the feature `dlopen`.");
+    ExitCode::FAILURE
+}
+
+#[cfg(not(feature = "dlopen"))]
+fn main() -> ExitCode {
+    let mut ctx = ptr::null_mut();
+    let (t, y1) = unsafe {
+        if SUNContext_Create(comm_no_mpi(), &mut ctx) < 0 {
+            eprintln!("Could not initialize the SUNDIALS context.");
+            return ExitCode::FAILURE;
+        }
+        let y = N_VNew_Serial(1, ctx);
+        *N_VGetArrayPointer(y) = 1.;
+        let mut cvode_mem = CVodeCreate(CV_ADAMS, ctx);
+        CVodeInit(cvode_mem, Some(rhs), 0., y);
+        CVodeSStolerances(cvode_mem, 1e-8, 1e-10);
+        let matrix = SUNDenseMatrix(1, 1, ctx);
+        let solver = SUNLinSol_Dense(y, matrix, ctx);
+        CVodeSetLinearSolver(cvode_mem, solver, matrix);
+
+        let mut t = 0.;
+        CVode(cvode_mem, 1., y, &mut t, CV_NORMAL);
+        let y1 = *N_VGetArrayPointer(y);
+
+        N_VDestroy(y);
+        CVodeFree(&mut cvode_mem);
+        SUNLinSolFree(solver);
+        SUNMatDestroy(matrix);
+        SUNContext_Free(&mut ctx);
+        (t, y1)
+    };
+    let exact = (-t).exp();
+    println!("y({}) = {} (exact: {})", t, y1, exact);
+    if (y1 - exact).abs() < 1e-6 {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("Wrong solution!");
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,59 @@
+//! Print information about the SUNDIALS library sundials-sys is
+//! linked to, to check the environment:
+//!
+//!     cargo run --example info
+
+use std::ffi::{c_char, c_int, CStr};
+use std::mem::size_of;
+use sundials_sys::*;
+
+fn main() {
+    let mut version: [c_char; 32] = [0; 32];
+    let (mut major, mut minor, mut patch) = (0, 0, 0);
+    let mut label: [c_char; 32] = [0; 32];
+    #[cfg(not(feature = "dlopen"))]
+    unsafe {
+        SUNDIALSGetVersion(version.as_mut_ptr(), version.len() as c_int);
+        SUNDIALSGetVersionNumber(&mut major, &mut minor, &mut patch,
+                                 label.as_mut_ptr(), label.len() as c_int);
+    }
+    let version = unsafe { CStr::from_ptr(version.as_ptr()) };
+    let label = unsafe { CStr::from_ptr(label.as_ptr()) };
+    println!("SUNDIALS version: {} ({}.{}.{}{}{})", version.to_string_lossy(),
+             major, minor, patch,
+             if label.is_empty() { "" } else { "-" }, label.to_string_lossy());
+    println!("Bindings generated for: {}.{}.{}", SUNDIALS_VERSION_MAJOR,
+             SUNDIALS_VERSION_MINOR, SUNDIALS_VERSION_PATCH);
+    let precision = match size_of::<realtype>() {
+        4 => "single",
+        8 => "double",
+        _ => "extended",
+    };
+    println!("Precision: {} ({} bytes)", precision, size_of::<realtype>());
+    println!("Index size: {} bits", 8 * size_of::<sunindextype>());
+    #[cfg(not(feature = "dlopen"))]
+    match verify_configuration() {
+        Ok(()) => println!("Library matches the bindings"),
+        Err(e) => println!("{}", e),
+    }
+
+    let mut modules = vec![];
+    macro_rules! feature { ($($f:tt),*) => {
+        $(if cfg!(feature = $f) { modules.push($f) })*
+    }}
+    feature!("arkode", "cvode", "cvodes", "ida", "idas", "kinsol");
+    println!("Solvers: {}", modules.join(", "));
+    let mut features = vec![];
+    macro_rules! feature { ($($f:tt),*) => {
+        $(if cfg!(feature = $f) { features.push($f) })*
+    }}
+    feature!("klu", "lapack", "superlumt", "superludist", "magma", "onemkl",
+             "nvecopenmp", "nvecopenmpdev", "nvecpthreads", "nvecparallel",
+             "nveccuda", "nvechip", "nvecsycl", "nvecraja", "kokkos",
+             "ginkgo", "cusparse", "cusolversp", "petsc", "hypre",
+             "trilinos", "xbraid", "nvecmanyvector", "nvecmpimanyvector",
+             "nvecmpiplusx", "monitoring", "profiling", "caliper",
+             "logging_info", "logging_debug", "static_libraries",
+             "static_all", "build_libraries", "dlopen");
+    println!("Other features: {}", features.join(", "));
+}
@@ -10,32 +10,632 @@ include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 // The communicator type changed from version 6 to 7.
 
 /// Communicator connection type.
-#[cfg(all(sundials_version_major = "6", not(feature="nvecopenmp")))]
+#[cfg(sundials_version_major = "6")]
 pub type SUNComm = *mut std::ffi::c_void;
 
 /// Create a new communicator type when MPI is not enabled.
-#[cfg(all(sundials_version_major = "6", not(feature="nvecopenmp")))]
+#[cfg(sundials_version_major = "6")]
 pub fn comm_no_mpi() -> SUNComm { std::ptr::null_mut() }
 
 /// Create a new communicator type when MPI is not enabled.
-#[cfg(all(sundials_version_major = "7", not(feature="nvecopenmp")))]
+#[cfg(all(sundials_version_major = "7", not(sundials_has_mpi)))]
 pub fn comm_no_mpi() -> SUNComm { SUN_COMM_NULL }
 
+// When SUNDIALS is built with MPI, the communicators are given by
+// a small C library compiled by the build script.
+#[cfg(sundials_has_mpi)]
+extern "C" {
+    #[cfg(sundials_version_major = "7")]
+    fn sundials_sys_comm_null() -> SUNComm;
+    fn sundials_sys_comm_world() -> SUNComm;
+}
+
+/// Create a new communicator type when MPI is not used
+/// (`MPI_COMM_NULL` as SUNDIALS was built with MPI).
+#[cfg(all(sundials_version_major = "7", sundials_has_mpi))]
+pub fn comm_no_mpi() -> SUNComm { unsafe { sundials_sys_comm_null() } }
+
+/// Communicator of all processes (`MPI_COMM_WORLD`).  MPI must have
+/// been initialized (e.g. by the `mpi` crate).
+#[cfg(sundials_has_mpi)]
+pub fn comm_world() -> SUNComm { unsafe { sundials_sys_comm_world() } }
+
+/// Communicator for SUNDIALS of the communicator `comm` of the `mpi`
+/// crate, e.g. `comm_from_rsmpi(&mpi::initialize().unwrap().world())`.
+#[cfg(all(feature = "rsmpi", sundials_has_mpi,
+          sundials_version_major = "7"))]
+pub fn comm_from_rsmpi<C: mpi::topology::Communicator>(comm: &C) -> SUNComm {
+    use mpi::raw::AsRaw;
+    // Both are the `MPI_Comm` of the C library.
+    unsafe { std::mem::transmute::<mpi::ffi::MPI_Comm, SUNComm>(comm.as_raw()) }
+}
+
+/// Communicator for SUNDIALS of the communicator `comm` of the `mpi`
+/// crate.  SUNDIALS 6 takes a pointer to the `MPI_Comm`, which is
+/// allocated here and never freed.
+#[cfg(all(feature = "rsmpi", sundials_has_mpi,
+          sundials_version_major = "6"))]
+pub fn comm_from_rsmpi<C: mpi::topology::Communicator>(comm: &C) -> SUNComm {
+    use mpi::raw::AsRaw;
+    Box::leak(Box::new(comm.as_raw())) as *mut _ as SUNComm
+}
+
+/// Communicator of all processes.  As SUNDIALS was built without MPI,
+/// this is the same as [`comm_no_mpi`].
+#[cfg(not(sundials_has_mpi))]
+pub fn comm_world() -> SUNComm { comm_no_mpi() }
+
 /// Backward compatibility type.
-#[cfg(sundials_version_major = "7")]
+#[cfg(any(sundials_version_major = "7", sundials_long_double))]
 pub type realtype = sunrealtype; // namespaced, so no prefix needed.
 
+/// Integer type of SUNDIALS for indices and lengths, `i64` unless it
+/// was built with `SUNDIALS_INDEX_SIZE=32` (cfg `sundials_index_size`).
+#[cfg(sundials_index_size = "32")]
+pub type sunindextype = i32;
+/// Integer type of SUNDIALS for indices and lengths, `i64` unless it
+/// was built with `SUNDIALS_INDEX_SIZE=32` (cfg `sundials_index_size`).
+#[cfg(not(sundials_index_size = "32"))]
+pub type sunindextype = i64;
+
+/// Floating point type of SUNDIALS built in extended precision: the C
+/// `long double`, which Rust lacks.  Its values can be stored, passed
+/// by pointer and converted from and to `f64`.  However, SUNDIALS
+/// functions taking or returning a `realtype` *by value* cannot be
+/// called correctly from Rust (the C ABI passes `long double` in x87
+/// or vector registers), use a C shim for them.
+#[cfg(sundials_long_double)]
+pub type sunrealtype = c_longdouble;
+
+/// C `long double`: the x87 80 bits extended format on x86 and
+/// x86_64, IEEE binary128 elsewhere.
+#[cfg(sundials_long_double)]
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(target_arch = "x86", repr(C, align(4)))]
+#[cfg_attr(not(target_arch = "x86"), repr(C, align(16)))]
+pub struct c_longdouble(
+    #[cfg(target_arch = "x86")] [u8; 12],
+    #[cfg(not(target_arch = "x86"))] [u8; 16],
+);
+
+#[cfg(sundials_long_double)]
+impl c_longdouble {
+    /// Multiply `x` by 2^`k`.
+    fn scale(mut x: f64, mut k: i32) -> f64 {
+        while k > 1000 { x *= 2f64.powi(1000); k -= 1000 }
+        while k < -1000 { x *= 2f64.powi(-1000); k += 1000 }
+        x * 2f64.powi(k)
+    }
+
+    /// Sign, biased exponent (bias 16383, 0x7fff for infinities and
+    /// NaN) and significand of `x`, the significand having its leading
+    /// 1 at bit 63 (or being 0 if `x` is zero).
+    fn decompose(x: f64) -> (bool, i32, u64) {
+        let bits = x.to_bits();
+        let sign = bits >> 63 == 1;
+        let e = ((bits >> 52) & 0x7ff) as i32;
+        let frac = bits & ((1 << 52) - 1);
+        if e == 0x7ff {
+            // Significand with bit 63 set, NaN payload below.
+            (sign, 0x7fff, 1 << 63 | frac << 11)
+        } else if e == 0 && frac == 0 {
+            (sign, 0, 0)
+        } else if e == 0 {
+            let lz = frac.leading_zeros() as i32;
+            (sign, 16383 - 1074 + (63 - lz), frac << lz)
+        } else {
+            (sign, e - 1023 + 16383, 1 << 63 | frac << 11)
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub fn from_f64(x: f64) -> Self {
+        let (sign, e, m) = Self::decompose(x);
+        let se = (sign as u16) << 15 | e as u16;
+        let mut b = Self::default();
+        b.0[..8].copy_from_slice(&m.to_le_bytes());
+        b.0[8..10].copy_from_slice(&se.to_le_bytes());
+        b
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub fn to_f64(self) -> f64 {
+        let m = u64::from_le_bytes(self.0[..8].try_into().unwrap());
+        let se = u16::from_le_bytes(self.0[8..10].try_into().unwrap());
+        let e = (se & 0x7fff) as i32;
+        let x = if e == 0x7fff {
+            if m << 1 == 0 { f64::INFINITY } else { f64::NAN }
+        } else {
+            // Denormals have e = 0 but the exponent of e = 1.
+            Self::scale(m as f64, e.max(1) - 16383 - 63)
+        };
+        if se >> 15 == 1 { -x } else { x }
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    pub fn from_f64(x: f64) -> Self {
+        let (sign, e, m) = Self::decompose(x);
+        // The leading 1 is implicit in binary128.
+        let frac = ((m as u128) << 49) & ((1 << 112) - 1);
+        let bits = (sign as u128) << 127 | (e as u128) << 112 | frac;
+        Self(bits.to_ne_bytes())
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    pub fn to_f64(self) -> f64 {
+        let bits = u128::from_ne_bytes(self.0);
+        let e = ((bits >> 112) & 0x7fff) as i32;
+        let frac = bits & ((1 << 112) - 1);
+        let x = if e == 0x7fff {
+            if frac == 0 { f64::INFINITY } else { f64::NAN }
+        } else if e == 0 {
+            Self::scale(frac as f64, 1 - 16383 - 112)
+        } else {
+            Self::scale((1 << 112 | frac) as f64, e - 16383 - 112)
+        };
+        if bits >> 127 == 1 { -x } else { x }
+    }
+}
+
+#[cfg(sundials_long_double)]
+impl c_longdouble {
+    /// Bytes of the value, without the padding of the x87 format.
+    fn significant(&self) -> &[u8] {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        return &self.0[..10];
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        return &self.0[..];
+    }
+}
+
+/// IEEE equality: NaN is not equal to anything, `-0.0 == 0.0`.
+#[cfg(sundials_long_double)]
+impl PartialEq for c_longdouble {
+    fn eq(&self, other: &Self) -> bool {
+        if self.to_f64().is_nan() || other.to_f64().is_nan() {
+            return false
+        }
+        let zero = |x: &Self| {
+            x.significant() == Self::from_f64(0.0).significant()
+                || x.significant() == Self::from_f64(-0.0).significant()
+        };
+        self.significant() == other.significant() || (zero(self) && zero(other))
+    }
+}
 
-#[cfg(test)]
+#[cfg(sundials_long_double)]
+impl From<f64> for c_longdouble {
+    fn from(x: f64) -> Self { Self::from_f64(x) }
+}
+
+#[cfg(sundials_long_double)]
+impl From<c_longdouble> for f64 {
+    fn from(x: c_longdouble) -> Self { x.to_f64() }
+}
+
+#[cfg(sundials_long_double)]
+impl std::fmt::Debug for c_longdouble {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.to_f64())
+    }
+}
+
+// Other items of the version 6 API removed in version 7, so that code
+// written for version 6 still compiles.
+
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use c_int (`sunbooleantype` is a C macro)")]
+pub type booleantype = std::ffi::c_int;
+
+#[cfg(all(sundials_version_major = "7", not(sundials_long_double)))]
+#[deprecated(note = "use SUN_BIG_REAL")]
+pub const BIG_REAL: realtype = realtype::MAX;
+#[cfg(all(sundials_version_major = "7", not(sundials_long_double)))]
+#[deprecated(note = "use SUN_SMALL_REAL")]
+pub const SMALL_REAL: realtype = realtype::MIN_POSITIVE;
+#[cfg(all(sundials_version_major = "7", not(sundials_long_double)))]
+#[deprecated(note = "use SUN_UNIT_ROUNDOFF")]
+pub const UNIT_ROUNDOFF: realtype = realtype::EPSILON;
+
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUN_PREC_NONE")]
+pub const PREC_NONE: std::ffi::c_int = SUN_PREC_NONE as _;
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUN_PREC_LEFT")]
+pub const PREC_LEFT: std::ffi::c_int = SUN_PREC_LEFT as _;
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUN_PREC_RIGHT")]
+pub const PREC_RIGHT: std::ffi::c_int = SUN_PREC_RIGHT as _;
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUN_PREC_BOTH")]
+pub const PREC_BOTH: std::ffi::c_int = SUN_PREC_BOTH as _;
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUN_MODIFIED_GS")]
+pub const MODIFIED_GS: std::ffi::c_int = SUN_MODIFIED_GS as _;
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUN_CLASSICAL_GS")]
+pub const CLASSICAL_GS: std::ffi::c_int = SUN_CLASSICAL_GS as _;
+
+/// Error handler of the version 6 API (`CVErrHandlerFn`,
+/// `ARKErrHandlerFn`, `IDAErrHandlerFn`, `KINErrHandlerFn`).
+#[cfg(sundials_version_major = "7")]
+pub type ErrHandlerFn = Option<unsafe extern "C" fn(
+    error_code: std::ffi::c_int,
+    module: *const std::ffi::c_char,
+    function: *const std::ffi::c_char,
+    msg: *mut std::ffi::c_char,
+    user_data: *mut std::ffi::c_void,
+)>;
+
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUNErrHandlerFn")]
+pub type CVErrHandlerFn = ErrHandlerFn;
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUNErrHandlerFn")]
+pub type ARKErrHandlerFn = ErrHandlerFn;
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUNErrHandlerFn")]
+pub type IDAErrHandlerFn = ErrHandlerFn;
+#[cfg(sundials_version_major = "7")]
+#[deprecated(note = "use SUNErrHandlerFn")]
+pub type KINErrHandlerFn = ErrHandlerFn;
+
+/// Version 6 error handler called by [`v6_err_handler`].
+#[cfg(all(sundials_version_major = "7",
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+struct V6ErrHandler {
+    ehfun: unsafe extern "C" fn(
+        std::ffi::c_int, *const std::ffi::c_char, *const std::ffi::c_char,
+        *mut std::ffi::c_char, *mut std::ffi::c_void),
+    module: &'static std::ffi::CStr,
+    eh_data: *mut std::ffi::c_void,
+}
+
+#[cfg(all(sundials_version_major = "7",
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+unsafe extern "C" fn v6_err_handler(
+    _line: std::ffi::c_int, func: *const std::ffi::c_char,
+    _file: *const std::ffi::c_char, msg: *const std::ffi::c_char,
+    err_code: SUNErrCode, err_user_data: *mut std::ffi::c_void,
+    _sunctx: SUNContext,
+) {
+    let h = &*(err_user_data as *const V6ErrHandler);
+    (h.ehfun)(err_code, h.module.as_ptr(), func, msg as *mut _, h.eh_data);
+}
+
+/// Make `ehfun` (or, if `None`, the default handler printing the
+/// messages) the only error handler of the context of the integrator
+/// memory `mem`.  Each handler set this way is leaked.
+#[cfg(all(sundials_version_major = "7",
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+unsafe fn set_v6_err_handler(
+    mem: *mut std::ffi::c_void, module: &'static std::ffi::CStr,
+    ehfun: ErrHandlerFn, eh_data: *mut std::ffi::c_void,
+) -> std::ffi::c_int {
+    // The context is the first field of the memory structures of
+    // CVODE(S), ARKODE, IDA(S) and KINSOL.
+    let ctx = *(mem as *const SUNContext);
+    let ret = SUNContext_ClearErrHandlers(ctx);
+    if ret != 0 { return ret }
+    match ehfun {
+        None => SUNContext_PushErrHandler(ctx, Some(SUNLogErrHandlerFn),
+                                          std::ptr::null_mut()),
+        Some(ehfun) => {
+            let h = Box::new(V6ErrHandler { ehfun, module, eh_data });
+            SUNContext_PushErrHandler(ctx, Some(v6_err_handler),
+                                      Box::into_raw(h) as *mut _)
+        }
+    }
+}
+
+/// Version 6 `CVodeSetErrHandlerFn`.  With SUNDIALS 7, error handlers
+/// belong to the context: `ehfun` replaces those of the context of
+/// `cvode_mem` (and thus of the other objects sharing it) and is
+/// called with the SUNDIALS error code, "CVODE" as module and the
+/// name of the function.  Warnings go to the SUNDIALS logger instead.
+///
+/// # Safety
+/// `cvode_mem` must be null or created by `CVodeCreate`.
+#[cfg(all(sundials_version_major = "7",
+          any(feature = "cvode", feature = "cvodes"),
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+#[deprecated(note = "use SUNContext_PushErrHandler")]
+pub unsafe fn CVodeSetErrHandlerFn(
+    cvode_mem: *mut std::ffi::c_void, ehfun: ErrHandlerFn,
+    eh_data: *mut std::ffi::c_void,
+) -> std::ffi::c_int {
+    if cvode_mem.is_null() { return CV_MEM_NULL }
+    set_v6_err_handler(cvode_mem, c"CVODE", ehfun, eh_data)
+}
+
+/// Version 6 `ARKStepSetErrHandlerFn` (see [`CVodeSetErrHandlerFn`]).
+///
+/// # Safety
+/// `arkode_mem` must be null or created by `ARKStepCreate`.
+#[cfg(all(sundials_version_major = "7", feature = "arkode",
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+#[deprecated(note = "use SUNContext_PushErrHandler")]
+pub unsafe fn ARKStepSetErrHandlerFn(
+    arkode_mem: *mut std::ffi::c_void, ehfun: ErrHandlerFn,
+    eh_data: *mut std::ffi::c_void,
+) -> std::ffi::c_int {
+    if arkode_mem.is_null() { return ARK_MEM_NULL }
+    set_v6_err_handler(arkode_mem, c"ARKODE", ehfun, eh_data)
+}
+
+/// Version 6 `IDASetErrHandlerFn` (see [`CVodeSetErrHandlerFn`]).
+///
+/// # Safety
+/// `ida_mem` must be null or created by `IDACreate`.
+#[cfg(all(sundials_version_major = "7",
+          any(feature = "ida", feature = "idas"),
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+#[deprecated(note = "use SUNContext_PushErrHandler")]
+pub unsafe fn IDASetErrHandlerFn(
+    ida_mem: *mut std::ffi::c_void, ehfun: ErrHandlerFn,
+    eh_data: *mut std::ffi::c_void,
+) -> std::ffi::c_int {
+    if ida_mem.is_null() { return IDA_MEM_NULL }
+    set_v6_err_handler(ida_mem, c"IDA", ehfun, eh_data)
+}
+
+/// Version 6 `KINSetErrHandlerFn` (see [`CVodeSetErrHandlerFn`]).
+///
+/// # Safety
+/// `kinmem` must be null or created by `KINCreate`.
+#[cfg(all(sundials_version_major = "7", feature = "kinsol",
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+#[deprecated(note = "use SUNContext_PushErrHandler")]
+pub unsafe fn KINSetErrHandlerFn(
+    kinmem: *mut std::ffi::c_void, ehfun: ErrHandlerFn,
+    eh_data: *mut std::ffi::c_void,
+) -> std::ffi::c_int {
+    if kinmem.is_null() { return KIN_MEM_NULL }
+    set_v6_err_handler(kinmem, c"KINSOL", ehfun, eh_data)
+}
+
+/// Mismatch between the SUNDIALS the bindings were generated for and
+/// the library loaded at runtime (see [`verify_configuration`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationError {
+    /// What differs: "version", "precision" or "index size", or
+    /// "context" if the library could not create a `SUNContext`.
+    pub item: &'static str,
+    /// Value the bindings were generated for.
+    pub bindings: String,
+    /// Value for the loaded library.
+    pub library: String,
+}
+
+impl std::fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SUNDIALS {} mismatch: the bindings were generated for {} \
+                   but the loaded library has {} (check LD_LIBRARY_PATH, \
+                   PATH or DYLD_LIBRARY_PATH)",
+               self.item, self.bindings, self.library)
+    }
+}
+
+impl std::error::Error for ConfigurationError {}
+
+/// Version (major, minor, patch) of the SUNDIALS library the bindings
+/// were generated for.  The one loaded at runtime is given by
+/// `SUNDIALSGetVersionNumber` (see also [`verify_configuration`]).
+pub const SUNDIALS_LINKED_VERSION: (u32, u32, u32) = (
+    SUNDIALS_VERSION_MAJOR as u32,
+    SUNDIALS_VERSION_MINOR as u32,
+    SUNDIALS_VERSION_PATCH as u32,
+);
+
+/// Check that the SUNDIALS library loaded at runtime agrees with the
+/// one the bindings were generated for: same major and minor version
+/// (patch releases are compatible), same `realtype` and same
+/// `sunindextype`.  A different library may be picked up, e.g.
+/// through `LD_LIBRARY_PATH`, and calling it would corrupt memory.
+#[cfg(not(any(feature = "dlopen", feature = "headers_only")))]
+pub fn verify_configuration() -> Result<(), ConfigurationError> {
+    use std::ffi::{c_char, c_int};
+    use std::mem::size_of;
+
+    let (mut major, mut minor, mut patch) = (0, 0, 0);
+    let mut label: [c_char; 32] = [0; 32];
+    unsafe {
+        SUNDIALSGetVersionNumber(&mut major, &mut minor, &mut patch,
+                                 label.as_mut_ptr(), label.len() as c_int);
+    }
+    if (major, minor) != (SUNDIALS_VERSION_MAJOR, SUNDIALS_VERSION_MINOR) {
+        return Err(ConfigurationError {
+            item: "version",
+            bindings: format!("{}.{}.{}", SUNDIALS_VERSION_MAJOR,
+                              SUNDIALS_VERSION_MINOR, SUNDIALS_VERSION_PATCH),
+            library: format!("{}.{}.{}", major, minor, patch),
+        })
+    }
+
+    #[cfg(not(sundials_long_double))]
+    let (real, unreal) = (|x: f64| x as realtype, |x: realtype| x as f64);
+    #[cfg(sundials_long_double)]
+    let (real, unreal) = (realtype::from_f64, realtype::to_f64);
+    // Let the library work on vectors whose data is owned here, with
+    // enough room for 2 `long double` whatever `realtype` is.
+    let mut x: [realtype; 8] = [real(0.0); 8];
+    x[0] = real(2.0);
+    x[1] = real(4.0);
+    let mut z: [realtype; 8] = [real(0.0); 8];
+    // Second slots catch a library writing a larger `sunindextype`.
+    let mut lrw: [sunindextype; 2] = [-1; 2];
+    let mut liw: [sunindextype; 2] = [-1; 2];
+    unsafe {
+        let mut ctx = std::ptr::null_mut();
+        if SUNContext_Create(comm_no_mpi(), &mut ctx) != 0 {
+            return Err(ConfigurationError {
+                item: "context",
+                bindings: "a usable SUNContext".into(),
+                library: "failed to create it".into(),
+            })
+        }
+        let vx = N_VMake_Serial(2, x.as_mut_ptr(), ctx);
+        let vz = N_VMake_Serial(2, z.as_mut_ptr(), ctx);
+        N_VInv(vx, vz);
+        N_VSpace(vx, lrw.as_mut_ptr(), liw.as_mut_ptr());
+        N_VDestroy(vz);
+        N_VDestroy(vx);
+        SUNContext_Free(&mut ctx);
+    }
+
+    let bytes = size_of::<realtype>();
+    let z = [unreal(z[0]), unreal(z[1])];
+    if z != [0.5, 0.25] {
+        return Err(ConfigurationError {
+            item: "precision",
+            bindings: format!("{}-byte realtype", bytes),
+            library: format!("another realtype (1/[2, 4] gave [{}, {}])",
+                             z[0], z[1]),
+        })
+    }
+    let bits = 8 * size_of::<sunindextype>();
+    if lrw != [2, -1] || liw != [1, -1] {
+        return Err(ConfigurationError {
+            item: "index size",
+            bindings: format!("{}-bit sunindextype", bits),
+            library: format!("{}-bit sunindextype",
+                             if lrw[1] != -1 { 2 * bits } else { bits / 2 }),
+        })
+    }
+    Ok(())
+}
+
+/// Communicator of the vector `v`, `None` if it is not distributed
+/// (e.g. serial or OpenMP vectors).  With SUNDIALS 6, this is a
+/// pointer to the `MPI_Comm`.
+///
+/// # Safety
+/// `v` must be a valid vector.
+#[cfg(not(any(feature = "dlopen", feature = "headers_only")))]
+pub unsafe fn vector_comm(v: N_Vector) -> Option<SUNComm> {
+    let comm = N_VGetCommunicator(v);
+    if comm == comm_no_mpi() { None } else { Some(comm) }
+}
+
+/// Global reductions of a vector, see [`vector_summary`].
+#[cfg(not(sundials_long_double))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VectorSummary {
+    /// Length of the part of the vector owned by this process.
+    pub local_length: sunindextype,
+    /// Length of the whole vector.
+    pub global_length: sunindextype,
+    pub min: realtype,
+    pub max_norm: realtype,
+    pub l1_norm: realtype,
+    pub l2_norm: realtype,
+}
+
+/// Lengths, minimum and norms of the vector `v`.  For distributed
+/// vectors, this is a collective operation: it must be called by all
+/// processes of the communicator of `v`.
+///
+/// # Safety
+/// `v` must be a valid vector.
+#[cfg(all(not(any(feature = "dlopen", feature = "headers_only")),
+          not(sundials_long_double)))]
+pub unsafe fn vector_summary(v: N_Vector) -> VectorSummary {
+    VectorSummary {
+        local_length: N_VGetLocalLength(v),
+        global_length: N_VGetLength(v),
+        min: N_VMin(v),
+        max_norm: N_VMaxNorm(v),
+        l1_norm: N_VL1Norm(v),
+        l2_norm: N_VDotProd(v, v).sqrt(),
+    }
+}
+
+#[cfg(feature = "nvtx")]
+extern "C" {
+    fn nvtxRangePushA(message: *const std::ffi::c_char) -> std::ffi::c_int;
+    fn nvtxRangePop() -> std::ffi::c_int;
+}
+
+/// NVTX range, shown on Nsight timelines, lasting as long as the
+/// value lives.  Use it e.g. to mark user callbacks:
+///
+/// ```ignore
+/// let _range = sundials_sys::nvtx_range("rhs");
+/// ```
+#[cfg(feature = "nvtx")]
+pub struct NvtxRange(());
+
+/// Start a NVTX range named `name` (see [`NvtxRange`]).
+#[cfg(feature = "nvtx")]
+pub fn nvtx_range(name: &str) -> NvtxRange {
+    let name = std::ffi::CString::new(name.replace('\0', " ")).unwrap();
+    unsafe { nvtxRangePushA(name.as_ptr()) };
+    NvtxRange(())
+}
+
+#[cfg(feature = "nvtx")]
+impl Drop for NvtxRange {
+    fn drop(&mut self) {
+        unsafe { nvtxRangePop() };
+    }
+}
+
+/// Region of the SUNDIALS profiler of a context, timed as long as
+/// the value lives.  Use it to compare the time spent in user
+/// callbacks with the one spent in SUNDIALS:
+///
+/// ```ignore
+/// let _region = unsafe { sundials_sys::profiler_region(ctx, c"rhs") };
+/// ```
+#[cfg(all(sundials_has_profiling,
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+pub struct ProfilerRegion {
+    profiler: SUNProfiler,
+    name: &'static std::ffi::CStr,
+}
+
+/// Start the region `name` of the profiler of `ctx` (see
+/// [`ProfilerRegion`]).  The name must be `'static` because SUNDIALS
+/// keeps the pointer as the key of its timer.
+///
+/// # Safety
+/// `ctx` must be a valid context outliving the region.
+#[cfg(all(sundials_has_profiling,
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+pub unsafe fn profiler_region(
+    ctx: SUNContext, name: &'static std::ffi::CStr
+) -> ProfilerRegion {
+    let mut profiler = std::ptr::null_mut();
+    SUNContext_GetProfiler(ctx, &mut profiler);
+    if !profiler.is_null() {
+        SUNProfiler_Begin(profiler, name.as_ptr());
+    }
+    ProfilerRegion { profiler, name }
+}
+
+#[cfg(all(sundials_has_profiling,
+          not(any(feature = "dlopen", feature = "headers_only"))))]
+impl Drop for ProfilerRegion {
+    fn drop(&mut self) {
+        if !self.profiler.is_null() {
+            unsafe { SUNProfiler_End(self.profiler, self.name.as_ptr()) };
+        }
+    }
+}
+
+#[cfg(all(test, not(any(feature = "dlopen", feature = "headers_only"))))]
 mod tests {
     use crate::*;
+    #[allow(unused_imports)]
     use core:: {ffi::c_void, ptr};
 
     #[test]
+    #[cfg(all(any(feature = "cvode", feature = "cvodes"),
+              not(sundials_long_double)))]
     // This just tests if the most basic of all programs works. More tests to come soon.
     fn simple_ode() {
         unsafe extern "C" fn rhs(
-            _t: f64,
+            _t: realtype,
             y: N_Vector,
             dy: N_Vector,
             _user_data: *mut c_void,
@@ -62,12 +662,14 @@ mod tests {
 
             CVodeSetLinearSolver(cvode_mem, solver, matrix);
 
-            let mut t = 0f64;
+            let mut t = 0.;
             CVode(cvode_mem, 1.0, y, &mut t, CV_NORMAL);
             // y[0] is now exp(-1)
 
-            let result = (*N_VGetArrayPointer(y) * 1e6) as i32;
-            assert_eq!(result, 367879);
+            #[cfg(not(sundials_precision = "single"))]
+            assert_eq!((*N_VGetArrayPointer(y) * 1e6) as i32, 367879);
+            #[cfg(sundials_precision = "single")]
+            assert!((*N_VGetArrayPointer(y) - (-1f32).exp()).abs() < 1e-4);
 
             N_VDestroy(y);
             CVodeFree(&mut cvode_mem);
@@ -75,4 +677,39 @@ mod tests {
             SUNMatDestroy(matrix);
         }
     }
+
+    #[test]
+    #[cfg(not(sundials_long_double))]
+    fn serial_vector_summary() {
+        unsafe {
+            let mut ctx = ptr::null_mut();
+            assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+            let v = N_VNew_Serial(2, ctx);
+            let data = N_VGetArrayPointer(v);
+            *data = -3.;
+            *data.add(1) = 4.;
+            assert!(vector_comm(v).is_none());
+            assert_eq!(vector_summary(v), VectorSummary {
+                local_length: 2, global_length: 2, min: -3.,
+                max_norm: 4., l1_norm: 7., l2_norm: 5. });
+            N_VDestroy(v);
+            SUNContext_Free(&mut ctx);
+        }
+    }
+
+    #[test]
+    fn configuration_matches_library() {
+        assert_eq!(verify_configuration(), Ok(()));
+    }
+
+    #[test]
+    #[cfg(sundials_long_double)]
+    fn long_double_roundtrip() {
+        for x in [0., -0., 1., -2.5, 1e300, -1e-300, 5e-324, f64::MAX,
+                  f64::INFINITY, f64::NEG_INFINITY] {
+            let y = c_longdouble::from(x);
+            assert_eq!(f64::from(y).to_bits(), x.to_bits());
+        }
+        assert!(c_longdouble::from(f64::NAN).to_f64().is_nan());
+    }
 }
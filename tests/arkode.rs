@@ -0,0 +1,92 @@
+#![cfg(all(feature = "arkode", not(feature = "dlopen"),
+           not(sundials_long_double)))]
+use std::{ptr, ffi::{c_int, c_void}};
+use sundials_sys::*;
+
+const OMEGA: realtype = 50.;
+
+/// Multirate integration of a slowly decaying component u' = -u
+/// coupled to a fast oscillator v' = ω w, w' = -ω v.  The fast part
+/// is integrated by an explicit ARKStep (i.e., an ERK method)
+/// attached as MRIStep inner stepper.
+#[test]
+fn mristep_multirate() {
+    // y = [u, v, w]
+    unsafe extern "C" fn fs(
+        _t: realtype, y: N_Vector, ydot: N_Vector, _user_data: *mut c_void,
+    ) -> c_int {
+        let y = std::slice::from_raw_parts(N_VGetArrayPointer(y), 3);
+        let ydot = std::slice::from_raw_parts_mut(N_VGetArrayPointer(ydot), 3);
+        ydot[0] = - y[0];
+        ydot[1] = 0.;
+        ydot[2] = 0.;
+        0
+    }
+
+    unsafe extern "C" fn ff(
+        _t: realtype, y: N_Vector, ydot: N_Vector, _user_data: *mut c_void,
+    ) -> c_int {
+        let y = std::slice::from_raw_parts(N_VGetArrayPointer(y), 3);
+        let ydot = std::slice::from_raw_parts_mut(N_VGetArrayPointer(ydot), 3);
+        ydot[0] = 0.;
+        ydot[1] = OMEGA * y[2];
+        ydot[2] = - OMEGA * y[1];
+        0
+    }
+
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Serial(3, ctx);
+        assert!(! y.is_null());
+        let y0 = std::slice::from_raw_parts_mut(N_VGetArrayPointer(y), 3);
+        y0.copy_from_slice(&[1., 1., 0.]);
+
+        // Fast integrator and its inner stepper wrapper.
+        let mut inner_mem = ARKStepCreate(Some(ff), None, 0., y, ctx);
+        assert!(! inner_mem.is_null());
+        assert_eq!(ARKStepSetFixedStep(inner_mem, 1e-4), ARK_SUCCESS);
+        let mut inner_stepper = ptr::null_mut();
+        assert_eq!(ARKStepCreateMRIStepInnerStepper(
+            inner_mem, &mut inner_stepper), ARK_SUCCESS);
+        assert!(! inner_stepper.is_null());
+
+        // Slow integrator.
+        let mut arkode_mem = MRIStepCreate(
+            Some(fs), None, 0., y, inner_stepper, ctx);
+        assert!(! arkode_mem.is_null());
+        assert_eq!(MRIStepSetFixedStep(arkode_mem, 1e-2), ARK_SUCCESS);
+        let table = MRIStepCoupling_LoadTable(
+            ARKODE_MRITableID_ARKODE_MIS_KW3);
+        assert!(! table.is_null());
+        assert_eq!((*table).q, 3);
+        assert_eq!(MRIStepSetCoupling(arkode_mem, table), ARK_SUCCESS);
+
+        let tout = 1.;
+        let mut t = 0.;
+        assert_eq!(MRIStepEvolve(arkode_mem, tout, y, &mut t, ARK_NORMAL),
+                   ARK_SUCCESS);
+        let yt = std::slice::from_raw_parts(N_VGetArrayPointer(y), 3);
+        let exact = [(-t).exp(), (OMEGA * t).cos(), - (OMEGA * t).sin()];
+        for (yi, ei) in yt.iter().zip(exact) {
+            assert!((yi - ei).abs() < 1e-4, "{:?} ≠ {:?}", yt, exact);
+        }
+
+        // The selected coupling table is the one in use.
+        let mut current = ptr::null_mut();
+        assert_eq!(MRIStepGetCurrentCoupling(arkode_mem, &mut current),
+                   ARK_SUCCESS);
+        assert_eq!((*current).q, (*table).q);
+        assert_eq!((*current).stages, (*table).stages);
+        let mut nsteps = 0;
+        assert_eq!(MRIStepGetNumSteps(arkode_mem, &mut nsteps), ARK_SUCCESS);
+        assert!(nsteps >= 100);
+
+        MRIStepCoupling_Free(table);
+        MRIStepFree(&mut arkode_mem);
+        MRIStepInnerStepper_Free(&mut inner_stepper);
+        ARKStepFree(&mut inner_mem);
+        N_VDestroy(y);
+        SUNContext_Free(&mut ctx);
+    }
+}
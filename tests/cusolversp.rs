@@ -0,0 +1,47 @@
+#![cfg(all(feature = "cusolversp", not(feature = "dlopen"),
+           not(sundials_long_double)))]
+use std::{ptr, ffi::c_int};
+use sundials_sys::*;
+
+/// Solve two independent 1×1 systems with the batched QR solver.
+#[test]
+fn cusolversp_batch_qr() {
+    let mut ctx = ptr::null_mut();
+    let mut cusparse = ptr::null_mut();
+    let mut cusolver = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        assert_eq!(cusparseCreate(&mut cusparse),
+                   cusparseStatus_t_CUSPARSE_STATUS_SUCCESS);
+        assert_eq!(cusolverSpCreate(&mut cusolver),
+                   cusolverStatus_t_CUSOLVER_STATUS_SUCCESS);
+        let a = SUNMatrix_cuSparse_NewBlockCSR(2, 1, 1, 1, cusparse, ctx);
+        assert!(! a.is_null());
+        let mut data: [realtype; 2] = [2., 2.];
+        let mut row_ptrs: [c_int; 2] = [0, 1];
+        let mut col_idx: [c_int; 1] = [0];
+        assert_eq!(SUNMatrix_cuSparse_CopyToDevice(
+            a, data.as_mut_ptr(), row_ptrs.as_mut_ptr(),
+            col_idx.as_mut_ptr()), 0);
+        let x = N_VNew_Cuda(2, ctx);
+        let b = N_VNew_Cuda(2, ctx);
+        *N_VGetHostArrayPointer_Cuda(b) = 2.;
+        *N_VGetHostArrayPointer_Cuda(b).add(1) = 4.;
+        N_VCopyToDevice_Cuda(b);
+        let ls = SUNLinSol_cuSolverSp_batchQR(x, a, cusolver, ctx);
+        assert!(! ls.is_null());
+        assert_eq!(SUNLinSolInitialize(ls), 0);
+        assert_eq!(SUNLinSolSetup(ls, a), 0);
+        assert_eq!(SUNLinSolSolve(ls, a, x, b, 0.), 0);
+        N_VCopyFromDevice_Cuda(x);
+        let xp = N_VGetHostArrayPointer_Cuda(x);
+        assert_eq!([*xp, *xp.add(1)], [1., 2.]);
+        SUNLinSolFree(ls);
+        N_VDestroy(b);
+        N_VDestroy(x);
+        SUNMatDestroy(a);
+        cusolverSpDestroy(cusolver);
+        cusparseDestroy(cusparse);
+        SUNContext_Free(&mut ctx);
+    }
+}
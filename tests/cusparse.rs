@@ -0,0 +1,34 @@
+#![cfg(all(feature = "cusparse", not(feature = "dlopen"),
+           not(sundials_long_double)))]
+use std::{ptr, ffi::c_int};
+use sundials_sys::*;
+
+/// Copy a CSR matrix to the device and back.
+#[test]
+fn cusparse_csr_roundtrip() {
+    let mut ctx = ptr::null_mut();
+    let mut cusparse = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        assert_eq!(cusparseCreate(&mut cusparse),
+                   cusparseStatus_t_CUSPARSE_STATUS_SUCCESS);
+        let a = SUNMatrix_cuSparse_NewCSR(2, 2, 2, cusparse, ctx);
+        assert!(! a.is_null());
+        assert_eq!(SUNMatGetID(a), SUNMatrix_ID_SUNMATRIX_CUSPARSE);
+        assert_eq!(SUNMatrix_cuSparse_Rows(a), 2);
+        assert_eq!(SUNMatrix_cuSparse_NNZ(a), 2);
+        let mut data: [realtype; 2] = [1., 2.];
+        let mut row_ptrs: [c_int; 3] = [0, 1, 2];
+        let mut col_idx: [c_int; 2] = [0, 1];
+        assert_eq!(SUNMatrix_cuSparse_CopyToDevice(
+            a, data.as_mut_ptr(), row_ptrs.as_mut_ptr(),
+            col_idx.as_mut_ptr()), 0);
+        let mut back: [realtype; 2] = [0.; 2];
+        assert_eq!(SUNMatrix_cuSparse_CopyFromDevice(
+            a, back.as_mut_ptr(), ptr::null_mut(), ptr::null_mut()), 0);
+        assert_eq!(back, data);
+        SUNMatDestroy(a);
+        cusparseDestroy(cusparse);
+        SUNContext_Free(&mut ctx);
+    }
+}
@@ -1,10 +1,12 @@
+#![cfg(all(any(feature = "cvode", feature = "cvodes"),
+           not(feature = "dlopen"), not(sundials_long_double)))]
 use std::{ptr, ffi::{c_int, c_void}};
 use sundials_sys::*;
 
 #[test]
 fn cvode_create() {
     extern "C" fn f(
-        _t: f64, _nvy: N_Vector, _nvdy: N_Vector, _user_data: *mut c_void,
+        _t: realtype, _nvy: N_Vector, _nvdy: N_Vector, _user_data: *mut c_void,
     ) -> c_int {
         0
     }
@@ -35,3 +37,355 @@ fn cvode_create() {
         SUNContext_Free(&mut ctx);
     }
 }
+
+/// Method of lines discretization of the heat equation u_t = u_xx on
+/// (0,1) with homogeneous Dirichlet boundary conditions, solved with a
+/// banded direct solver and a user supplied banded Jacobian.
+#[test]
+fn cvode_heat_band() {
+    const N: sunindextype = 20; // Number of interior points.
+    const MU: sunindextype = 1; // Upper half-bandwidth.
+    const ML: sunindextype = 1; // Lower half-bandwidth.
+    const DX: realtype = 1. / (N + 1) as realtype;
+
+    unsafe extern "C" fn f(
+        _t: realtype, y: N_Vector, ydot: N_Vector, _user_data: *mut c_void,
+    ) -> c_int {
+        let n = N as usize;
+        let y = std::slice::from_raw_parts(N_VGetArrayPointer(y), n);
+        let ydot = std::slice::from_raw_parts_mut(N_VGetArrayPointer(ydot), n);
+        for i in 0 .. n {
+            let left = if i == 0 { 0. } else { y[i - 1] };
+            let right = if i == n - 1 { 0. } else { y[i + 1] };
+            ydot[i] = (left - 2. * y[i] + right) / (DX * DX);
+        }
+        0
+    }
+
+    unsafe extern "C" fn jac(
+        _t: realtype, _y: N_Vector, _fy: N_Vector, jac: SUNMatrix,
+        _user_data: *mut c_void,
+        _tmp1: N_Vector, _tmp2: N_Vector, _tmp3: N_Vector,
+    ) -> c_int {
+        for j in 0 .. N {
+            // The column pointer points to the diagonal element so
+            // that the element (i,j) is at offset i - j.
+            let col = SUNBandMatrix_Column(jac, j);
+            *col = -2. / (DX * DX);
+            if j > 0 {
+                *col.offset(-1) = 1. / (DX * DX);
+            }
+            if j < N - 1 {
+                *col.offset(1) = 1. / (DX * DX);
+            }
+        }
+        0
+    }
+
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Serial(N, ctx);
+        assert!(! y.is_null());
+        let pi = std::f64::consts::PI as realtype;
+        let y0 = std::slice::from_raw_parts_mut(
+            N_VGetArrayPointer(y), N as usize);
+        for (i, yi) in y0.iter_mut().enumerate() {
+            *yi = (pi * (i + 1) as realtype * DX).sin();
+        }
+
+        let mut cvode_mem = CVodeCreate(CV_BDF, ctx);
+        assert!(! cvode_mem.is_null());
+        assert_eq!(CVodeInit(cvode_mem, Some(f), 0., y), CV_SUCCESS);
+        assert_eq!(CVodeSStolerances(cvode_mem, 1e-8, 1e-10), CV_SUCCESS);
+
+        let a = SUNBandMatrix(N, MU, ML, ctx);
+        assert!(! a.is_null());
+        assert_eq!(SUNBandMatrix_Rows(a), N);
+        assert_eq!(SUNBandMatrix_Columns(a), N);
+        assert_eq!(SUNBandMatrix_UpperBandwidth(a), MU);
+        assert_eq!(SUNBandMatrix_LowerBandwidth(a), ML);
+        // Extra storage is reserved for the LU factorization.
+        let smu = SUNBandMatrix_StoredUpperBandwidth(a);
+        assert_eq!(smu, (N - 1).min(MU + ML));
+        assert_eq!(SUNBandMatrix_LDim(a), smu + ML + 1);
+
+        let ls = SUNLinSol_Band(y, a, ctx);
+        assert!(! ls.is_null());
+        assert_eq!(CVodeSetLinearSolver(cvode_mem, ls, a), CVLS_SUCCESS as i32);
+        assert_eq!(CVodeSetJacFn(cvode_mem, Some(jac)), CVLS_SUCCESS as i32);
+
+        let tout = 0.1;
+        let mut t = 0.;
+        assert_eq!(CVode(cvode_mem, tout, y, &mut t, CV_NORMAL), CV_SUCCESS);
+
+        // sin(πx) is an eigenvector of the discrete Laplacian.
+        let lambda = - 4. / (DX * DX) * (pi * DX / 2.).sin().powi(2);
+        let yt = std::slice::from_raw_parts(N_VGetArrayPointer(y), N as usize);
+        for (i, yi) in yt.iter().enumerate() {
+            let exact = (lambda * t).exp()
+                * (pi * (i + 1) as realtype * DX).sin();
+            assert!((yi - exact).abs() <= 1e-5 * exact.abs() + 1e-8,
+                    "y[{}] = {} ≠ {}", i, yi, exact);
+        }
+
+        let mut njevals = 0;
+        assert_eq!(CVodeGetNumJacEvals(cvode_mem, &mut njevals),
+                   CVLS_SUCCESS as i32);
+        assert!(njevals > 0);
+
+        N_VDestroy(y);
+        CVodeFree(&mut cvode_mem);
+        SUNLinSolFree(ls);
+        SUNMatDestroy(a);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+/// Rootfinding on y' = -y, y(0) = 1, with the two threshold events
+/// y = 1/2 and y = 1/4.
+#[test]
+fn cvode_rootfinding() {
+    unsafe extern "C" fn f(
+        _t: realtype, y: N_Vector, ydot: N_Vector, _user_data: *mut c_void,
+    ) -> c_int {
+        *N_VGetArrayPointer(ydot) = - *N_VGetArrayPointer(y);
+        0
+    }
+
+    unsafe extern "C" fn g(
+        _t: realtype, y: N_Vector, gout: *mut realtype,
+        _user_data: *mut c_void,
+    ) -> c_int {
+        let y = *N_VGetArrayPointer(y);
+        *gout = y - 0.5;
+        *gout.offset(1) = y - 0.25;
+        0
+    }
+
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Serial(1, ctx);
+        assert!(! y.is_null());
+        *N_VGetArrayPointer(y) = 1.;
+
+        let mut cvode_mem = CVodeCreate(CV_BDF, ctx);
+        assert!(! cvode_mem.is_null());
+        assert_eq!(CVodeInit(cvode_mem, Some(f), 0., y), CV_SUCCESS);
+        assert_eq!(CVodeSStolerances(cvode_mem, 1e-8, 1e-10), CV_SUCCESS);
+        let a = SUNDenseMatrix(1, 1, ctx);
+        let ls = SUNLinSol_Dense(y, a, ctx);
+        assert_eq!(CVodeSetLinearSolver(cvode_mem, ls, a), CVLS_SUCCESS as i32);
+        assert_eq!(CVodeRootInit(cvode_mem, 2, Some(g)), CV_SUCCESS);
+
+        let tout = 2.;
+        let mut t = 0.;
+        let mut rootsfound: [c_int; 2] = [0; 2];
+        // Both roots are found, in order, with a decreasing direction.
+        for (troot, expected) in [(2f64.ln(), [-1, 0]), (4f64.ln(), [0, -1])] {
+            let r = CVode(cvode_mem, tout, y, &mut t, CV_NORMAL);
+            assert_eq!(r, CV_ROOT_RETURN);
+            assert!((t - troot as realtype).abs() < 1e-6,
+                    "t = {} ≠ {}", t, troot);
+            assert_eq!(CVodeGetRootInfo(cvode_mem, rootsfound.as_mut_ptr()),
+                       CV_SUCCESS);
+            assert_eq!(rootsfound, expected);
+        }
+        // Continue past the events up to `tout`.
+        let r = CVode(cvode_mem, tout, y, &mut t, CV_NORMAL);
+        assert_eq!(r, CV_SUCCESS);
+        assert_eq!(t, tout);
+        let yt = *N_VGetArrayPointer(y);
+        assert!((yt - (-tout).exp()).abs() < 1e-6);
+
+        let mut ngevals = 0;
+        assert_eq!(CVodeGetNumGEvals(cvode_mem, &mut ngevals), CV_SUCCESS);
+        assert!(ngevals > 0);
+
+        N_VDestroy(y);
+        CVodeFree(&mut cvode_mem);
+        SUNLinSolFree(ls);
+        SUNMatDestroy(a);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+/// The monitor function is called every `CVodeSetMonitorFrequency`
+/// steps when SUNDIALS is built with monitoring.
+#[test]
+#[cfg(sundials_has_monitoring)]
+fn cvode_monitor() {
+    unsafe extern "C" fn f(
+        _t: realtype, y: N_Vector, ydot: N_Vector, _user_data: *mut c_void,
+    ) -> c_int {
+        *N_VGetArrayPointer(ydot) = - *N_VGetArrayPointer(y);
+        0
+    }
+
+    unsafe extern "C" fn monitor(
+        _cvode_mem: *mut c_void, user_data: *mut c_void,
+    ) -> c_int {
+        *(user_data as *mut i64) += 1;
+        0
+    }
+
+    let mut ctx = ptr::null_mut();
+    let mut calls: i64 = 0;
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Serial(1, ctx);
+        *N_VGetArrayPointer(y) = 1.;
+        let mut cvode_mem = CVodeCreate(CV_BDF, ctx);
+        assert_eq!(CVodeInit(cvode_mem, Some(f), 0., y), CV_SUCCESS);
+        assert_eq!(CVodeSStolerances(cvode_mem, 1e-6, 1e-8), CV_SUCCESS);
+        let a = SUNDenseMatrix(1, 1, ctx);
+        let ls = SUNLinSol_Dense(y, a, ctx);
+        assert_eq!(CVodeSetLinearSolver(cvode_mem, ls, a), CVLS_SUCCESS as i32);
+        assert_eq!(CVodeSetUserData(cvode_mem,
+                                    &mut calls as *mut i64 as *mut c_void),
+                   CV_SUCCESS);
+        assert_eq!(CVodeSetMonitorFn(cvode_mem, Some(monitor)), CV_SUCCESS);
+        assert_eq!(CVodeSetMonitorFrequency(cvode_mem, 2), CV_SUCCESS);
+
+        let mut t = 0.;
+        assert_eq!(CVode(cvode_mem, 1., y, &mut t, CV_NORMAL), CV_SUCCESS);
+        let mut nsteps = 0;
+        assert_eq!(CVodeGetNumSteps(cvode_mem, &mut nsteps), CV_SUCCESS);
+        assert_eq!(calls, nsteps as i64 / 2);
+
+        N_VDestroy(y);
+        CVodeFree(&mut cvode_mem);
+        SUNLinSolFree(ls);
+        SUNMatDestroy(a);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+/// Reaction-diffusion system solved with SPGMR, left preconditioned
+/// by the diagonal of the Newton matrix I - γJ:
+///   u_t = u_xx - k u + k v
+///   v_t = v_xx + k u - k v
+/// on (0,1) with homogeneous Dirichlet boundary conditions.
+#[test]
+fn cvode_spgmr_preconditioner() {
+    const N: usize = 20; // Number of interior points.
+    const K: realtype = 10.;
+    const DX: realtype = 1. / (N + 1) as realtype;
+
+    struct UserData {
+        // Diagonal of I - γJ.
+        p: N_Vector,
+        setups: usize,
+        solves: usize,
+    }
+
+    // The unknowns are interleaved: y = [u_0, v_0, u_1, v_1,...].
+    unsafe extern "C" fn f(
+        _t: realtype, y: N_Vector, ydot: N_Vector, _user_data: *mut c_void,
+    ) -> c_int {
+        let y = std::slice::from_raw_parts(N_VGetArrayPointer(y), 2 * N);
+        let ydot = std::slice::from_raw_parts_mut(
+            N_VGetArrayPointer(ydot), 2 * N);
+        for i in 0 .. N {
+            for c in 0 .. 2 {
+                let k = 2 * i + c;
+                let left = if i == 0 { 0. } else { y[k - 2] };
+                let right = if i == N - 1 { 0. } else { y[k + 2] };
+                let other = y[2 * i + 1 - c];
+                ydot[k] = (left - 2. * y[k] + right) / (DX * DX)
+                    - K * y[k] + K * other;
+            }
+        }
+        0
+    }
+
+    unsafe extern "C" fn psetup(
+        _t: realtype, _y: N_Vector, _fy: N_Vector, _jok: c_int,
+        jcur: *mut c_int, gamma: realtype, user_data: *mut c_void,
+    ) -> c_int {
+        let data = &mut *(user_data as *mut UserData);
+        data.setups += 1;
+        N_VConst(1. + gamma * (2. / (DX * DX) + K), data.p);
+        *jcur = 1;
+        0
+    }
+
+    unsafe extern "C" fn psolve(
+        _t: realtype, _y: N_Vector, _fy: N_Vector, r: N_Vector, z: N_Vector,
+        _gamma: realtype, _delta: realtype, lr: c_int,
+        user_data: *mut c_void,
+    ) -> c_int {
+        assert_eq!(lr, 1, "Only left preconditioning was requested");
+        let data = &mut *(user_data as *mut UserData);
+        data.solves += 1;
+        N_VDiv(r, data.p, z);
+        0
+    }
+
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Serial(2 * N as sunindextype, ctx);
+        assert!(! y.is_null());
+        let pi = std::f64::consts::PI as realtype;
+        let y0 = std::slice::from_raw_parts_mut(N_VGetArrayPointer(y), 2 * N);
+        for i in 0 .. N {
+            let s = (pi * (i + 1) as realtype * DX).sin();
+            y0[2 * i] = s;
+            y0[2 * i + 1] = 0.5 * s;
+        }
+        let mut data = UserData {
+            p: N_VClone(y), setups: 0, solves: 0 };
+
+        let mut cvode_mem = CVodeCreate(CV_BDF, ctx);
+        assert!(! cvode_mem.is_null());
+        assert_eq!(CVodeInit(cvode_mem, Some(f), 0., y), CV_SUCCESS);
+        assert_eq!(CVodeSStolerances(cvode_mem, 1e-8, 1e-10), CV_SUCCESS);
+        assert_eq!(CVodeSetUserData(
+            cvode_mem, &mut data as *mut UserData as *mut c_void), CV_SUCCESS);
+
+        let ls = SUNLinSol_SPGMR(y, SUN_PREC_LEFT as _, 0, ctx);
+        assert!(! ls.is_null());
+        assert_eq!(CVodeSetLinearSolver(cvode_mem, ls, ptr::null_mut()),
+                   CVLS_SUCCESS as i32);
+        assert_eq!(CVodeSetPreconditioner(cvode_mem, Some(psetup), Some(psolve)),
+                   CVLS_SUCCESS as i32);
+
+        let tout = 0.1;
+        let mut t = 0.;
+        assert_eq!(CVode(cvode_mem, tout, y, &mut t, CV_NORMAL), CV_SUCCESS);
+
+        // The sine mode decouples the system: u + v decays at rate λ
+        // and u - v at rate λ - 2k, where λ is the eigenvalue of the
+        // discrete Laplacian.
+        let lambda = - 4. / (DX * DX) * (pi * DX / 2.).sin().powi(2);
+        let sum = 1.5 * (lambda * t).exp();
+        let diff = 0.5 * ((lambda - 2. * K) * t).exp();
+        let yt = std::slice::from_raw_parts(N_VGetArrayPointer(y), 2 * N);
+        for i in 0 .. N {
+            let s = (pi * (i + 1) as realtype * DX).sin();
+            let (u, v) = ((sum + diff) / 2. * s, (sum - diff) / 2. * s);
+            assert!((yt[2 * i] - u).abs() <= 1e-5 * u.abs() + 1e-8,
+                    "u[{}] = {} ≠ {}", i, yt[2 * i], u);
+            assert!((yt[2 * i + 1] - v).abs() <= 1e-5 * v.abs() + 1e-8,
+                    "v[{}] = {} ≠ {}", i, yt[2 * i + 1], v);
+        }
+
+        // The Rust callbacks were effectively used by CVLS.
+        let (mut npe, mut nps) = (0, 0);
+        assert_eq!(CVodeGetNumPrecEvals(cvode_mem, &mut npe),
+                   CVLS_SUCCESS as i32);
+        assert_eq!(CVodeGetNumPrecSolves(cvode_mem, &mut nps),
+                   CVLS_SUCCESS as i32);
+        assert!(npe > 0 && nps > 0);
+        assert_eq!(npe as usize, data.setups);
+        assert_eq!(nps as usize, data.solves);
+
+        N_VDestroy(data.p);
+        N_VDestroy(y);
+        CVodeFree(&mut cvode_mem);
+        SUNLinSolFree(ls);
+        SUNContext_Free(&mut ctx);
+    }
+}
@@ -0,0 +1,63 @@
+#![cfg(all(feature = "klu", not(feature = "dlopen"),
+           not(sundials_long_double)))]
+use std::{ptr, ffi::c_int};
+use sundials_sys::*;
+
+/// Set the 2×2 CSC matrix `a` to `d` times the identity.
+unsafe fn diagonal(a: SUNMatrix, d: realtype) {
+    let ptrs = SUNSparseMatrix_IndexPointers(a);
+    let vals = SUNSparseMatrix_IndexValues(a);
+    let data = SUNSparseMatrix_Data(a);
+    for i in 0..2 {
+        *ptrs.add(i) = i as sunindextype;
+        *vals.add(i) = i as sunindextype;
+        *data.add(i) = d;
+    }
+    *ptrs.add(2) = 2;
+}
+
+/// Solve `a x = b` with `ls` and return `x`.
+unsafe fn solve(
+    ls: SUNLinearSolver, a: SUNMatrix, x: N_Vector, b: N_Vector,
+) -> [realtype; 2] {
+    assert_eq!(SUNLinSolSetup(ls, a), 0);
+    assert_eq!(SUNLinSolSolve(ls, a, x, b, 0.), 0);
+    let x = N_VGetArrayPointer(x);
+    [*x, *x.add(1)]
+}
+
+/// Reuse the KLU symbolic factorization (partial re-initialization)
+/// or redo it (full re-initialization) after the matrix changed.
+#[test]
+fn klu_reinit() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let a = SUNSparseMatrix(2, 2, 2, CSC_MAT as c_int, ctx);
+        let x = N_VNew_Serial(2, ctx);
+        let b = N_VNew_Serial(2, ctx);
+        *N_VGetArrayPointer(b) = 2.;
+        *N_VGetArrayPointer(b).add(1) = 4.;
+        diagonal(a, 2.);
+        let ls = SUNLinSol_KLU(x, a, ctx);
+        assert!(! ls.is_null());
+        assert_eq!(SUNLinSol_KLUSetOrdering(ls, SUNKLU_ORDERING_DEFAULT), 0);
+        assert_eq!(SUNLinSolInitialize(ls), 0);
+        assert_eq!(solve(ls, a, x, b), [1., 2.]);
+
+        diagonal(a, 4.);
+        assert_eq!(SUNLinSol_KLUReInit(ls, a, 2, SUNKLU_REINIT_PARTIAL), 0);
+        assert_eq!(solve(ls, a, x, b), [0.5, 1.]);
+        assert!(! SUNLinSol_KLUGetSymbolic(ls).is_null());
+
+        diagonal(a, 0.5);
+        assert_eq!(SUNLinSol_KLUReInit(ls, a, 2, SUNKLU_REINIT_FULL), 0);
+        assert_eq!(solve(ls, a, x, b), [4., 8.]);
+
+        SUNLinSolFree(ls);
+        N_VDestroy(b);
+        N_VDestroy(x);
+        SUNMatDestroy(a);
+        SUNContext_Free(&mut ctx);
+    }
+}
@@ -0,0 +1,48 @@
+#![cfg(all(feature = "lapack", not(feature = "dlopen"),
+           not(sundials_long_double)))]
+use std::ptr;
+use sundials_sys::*;
+
+/// Solve a 3×3 tridiagonal system with the LAPACK dense and band
+/// linear solvers.
+#[test]
+fn lapack_dense_band() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let x = N_VNew_Serial(3, ctx);
+        let b = N_VNew_Serial(3, ctx);
+        let dense = SUNDenseMatrix(3, 3, ctx);
+        let band = SUNBandMatrix(3, 1, 1, ctx);
+        // A = [2 -1 0; -1 2 -1; 0 -1 2], A [1, 1, 1] = [1, 0, 1].
+        for i in 0..3 {
+            for j in i.saturating_sub(1)..(i + 2).min(3) {
+                let a = if i == j { 2. } else { -1. };
+                *SUNDenseMatrix_Column(dense, j).add(i as usize) = a;
+                *SUNBandMatrix_Column(band, j)
+                    .offset(i as isize - j as isize) = a;
+            }
+        }
+        for (ls, a) in [(SUNLinSol_LapackDense(x, dense, ctx), dense),
+                        (SUNLinSol_LapackBand(x, band, ctx), band)] {
+            assert!(! ls.is_null());
+            let bp = N_VGetArrayPointer(b);
+            *bp = 1.;
+            *bp.add(1) = 0.;
+            *bp.add(2) = 1.;
+            assert_eq!(SUNLinSolInitialize(ls), 0);
+            assert_eq!(SUNLinSolSetup(ls, a), 0);
+            assert_eq!(SUNLinSolSolve(ls, a, x, b, 0.), 0);
+            let xp = N_VGetArrayPointer(x);
+            for i in 0..3 {
+                assert!((*xp.add(i) - 1.).abs() < 1e-12);
+            }
+            SUNLinSolFree(ls);
+        }
+        SUNMatDestroy(band);
+        SUNMatDestroy(dense);
+        N_VDestroy(b);
+        N_VDestroy(x);
+        SUNContext_Free(&mut ctx);
+    }
+}
@@ -0,0 +1,29 @@
+#![cfg(all(any(sundials_logging_level = "3", sundials_logging_level = "4",
+               sundials_logging_level = "5"),
+           not(feature = "dlopen")))]
+use std::{ffi::{c_int, CString}, fs, ptr};
+use sundials_sys::*;
+
+/// Info messages reach the file set with `SUNLogger_SetInfoFilename`.
+#[test]
+fn logger_info_file() {
+    let path = std::env::temp_dir()
+        .join(format!("sundials-sys-log-{}.txt", std::process::id()));
+    let filename = CString::new(path.to_str().unwrap()).unwrap();
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let mut logger = ptr::null_mut();
+        assert_eq!(SUNContext_GetLogger(ctx, &mut logger), 0);
+        assert_eq!(SUNLogger_SetInfoFilename(logger, filename.as_ptr()), 0);
+        assert_eq!(SUNLogger_QueueMsg(logger, SUNLogLevel_SUN_LOGLEVEL_INFO,
+                                      c"test".as_ptr(), c"label".as_ptr(),
+                                      c"answer = %d".as_ptr(), 42 as c_int),
+                   0);
+        assert_eq!(SUNLogger_Flush(logger, SUNLogLevel_SUN_LOGLEVEL_INFO), 0);
+        SUNContext_Free(&mut ctx);
+    }
+    let log = fs::read_to_string(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    assert!(log.contains("answer = 42"), "log: {:?}", log);
+}
@@ -0,0 +1,341 @@
+#![cfg(all(any(feature = "nvecopenmp", feature = "nvecpthreads",
+               feature = "nvecparallel", feature = "nveccuda",
+               feature = "nvechip", feature = "nvecraja",
+               feature = "nvecopenmpdev", feature = "nvecmanyvector",
+               feature = "nvecmpiplusx", feature = "petsc",
+               feature = "hypre"),
+           not(feature = "dlopen"),
+           not(sundials_long_double)))]
+use std::{ptr, ffi::{c_int, c_void}};
+use sundials_sys::*;
+
+const N: usize = 100;
+
+/// Copy of the data of a vector between the host and the device.
+type HostDeviceCopy = unsafe extern "C" fn(N_Vector);
+
+/// Integrate y_i' = -y_i, y_i(0) = i, with CVODE on the vector `y`
+/// (whose context is `ctx`), reading and writing its components
+/// through `N_VGetArrayPointer`.
+#[allow(dead_code)]
+unsafe fn decay(y: N_Vector, ctx: SUNContext) {
+    decay_on_device(y, ctx, None)
+}
+
+/// Same as [`decay`] for a vector whose data lives on a device, with
+/// the functions copying it to and from the device.
+unsafe fn decay_on_device(
+    y: N_Vector, ctx: SUNContext, copy: Option<(HostDeviceCopy, HostDeviceCopy)>,
+) {
+    extern "C" fn f(
+        _t: realtype, y: N_Vector, ydot: N_Vector, _user_data: *mut c_void,
+    ) -> c_int {
+        unsafe { N_VScale(-1., y, ydot) };
+        0
+    }
+
+    assert_eq!(N_VGetLength(y), N as sunindextype);
+    let y0 = std::slice::from_raw_parts_mut(N_VGetArrayPointer(y), N);
+    for (i, yi) in y0.iter_mut().enumerate() {
+        *yi = i as realtype;
+    }
+    if let Some((to_device, _)) = copy {
+        to_device(y);
+    }
+
+    let mut cvode_mem = CVodeCreate(CV_ADAMS, ctx);
+    assert!(! cvode_mem.is_null());
+    assert_eq!(CVodeInit(cvode_mem, Some(f), 0., y), CV_SUCCESS);
+    assert_eq!(CVodeSStolerances(cvode_mem, 1e-8, 1e-10), CV_SUCCESS);
+    let nls = SUNNonlinSol_FixedPoint(y, 0, ctx);
+    assert!(! nls.is_null());
+    assert_eq!(CVodeSetNonlinearSolver(cvode_mem, nls), CV_SUCCESS);
+
+    let tout = 1.;
+    let mut t = 0.;
+    assert_eq!(CVode(cvode_mem, tout, y, &mut t, CV_NORMAL), CV_SUCCESS);
+    if let Some((_, from_device)) = copy {
+        from_device(y);
+    }
+    let yt = std::slice::from_raw_parts(N_VGetArrayPointer(y), N);
+    for (i, yi) in yt.iter().enumerate() {
+        let exact = i as realtype * (-t).exp();
+        assert!((yi - exact).abs() <= 1e-5 * exact + 1e-8,
+                "y[{}] = {} ≠ {}", i, yi, exact);
+    }
+
+    CVodeFree(&mut cvode_mem);
+    SUNNonlinSolFree(nls);
+}
+
+#[test]
+#[cfg(feature = "nvecopenmp")]
+fn nvecopenmp_decay() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_OpenMP(N as sunindextype, 4, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_OPENMP);
+        decay(y, ctx);
+        N_VDestroy(y);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "nvecpthreads")]
+fn nvecpthreads_decay() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Pthreads(N as sunindextype, 4, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_PTHREADS);
+        decay(y, ctx);
+        N_VDestroy(y);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+/// Initialize MPI (once for all the tests, which run in a single
+/// process) and return the communicator of all processes for SUNDIALS
+/// and as a `MPI_Comm`.
+#[cfg(feature = "nvecparallel")]
+unsafe fn mpi_world() -> (SUNComm, MPI_Comm) {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let mut provided = 0;
+        assert_eq!(MPI_Init_thread(ptr::null_mut(), ptr::null_mut(),
+                                   MPI_THREAD_MULTIPLE as c_int,
+                                   &mut provided), 0);
+    });
+    let comm = comm_world();
+    // With SUNDIALS 6, `SUNComm` points to the `MPI_Comm`.
+    #[cfg(sundials_version_major = "6")]
+    return (comm, *(comm as *mut MPI_Comm));
+    #[cfg(sundials_version_major = "7")]
+    return (comm, comm);
+}
+
+#[test]
+#[cfg(feature = "nvecparallel")]
+fn nvecparallel_decay() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        // A single process owning the whole vector.
+        let (comm, mpi_comm) = mpi_world();
+        assert!(SUNContext_Create(comm, &mut ctx) >= 0);
+        let y = N_VNew_Parallel(mpi_comm, N as sunindextype,
+                                N as sunindextype, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_PARALLEL);
+        assert!(vector_comm(y).is_some());
+        decay(y, ctx);
+        N_VDestroy(y);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "nveccuda")]
+fn nveccuda_decay() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Cuda(N as sunindextype, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_CUDA);
+        assert!(! N_VGetDeviceArrayPointer_Cuda(y).is_null());
+        decay_on_device(y, ctx,
+                        Some((N_VCopyToDevice_Cuda, N_VCopyFromDevice_Cuda)));
+        N_VDestroy(y);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "nvechip")]
+fn nvechip_decay() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Hip(N as sunindextype, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_HIP);
+        assert!(! N_VGetDeviceArrayPointer_Hip(y).is_null());
+        decay_on_device(y, ctx,
+                        Some((N_VCopyToDevice_Hip, N_VCopyFromDevice_Hip)));
+        N_VDestroy(y);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "nvecraja")]
+fn nvecraja_decay() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_Raja(N as sunindextype, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_RAJA);
+        decay_on_device(y, ctx,
+                        Some((N_VCopyToDevice_Raja, N_VCopyFromDevice_Raja)));
+        N_VDestroy(y);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "nvecopenmpdev")]
+fn nvecopenmpdev_decay() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let y = N_VNew_OpenMPDEV(N as sunindextype, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_OPENMPDEV);
+        decay_on_device(y, ctx, Some((N_VCopyToDevice_OpenMPDEV,
+                                      N_VCopyFromDevice_OpenMPDEV)));
+        N_VDestroy(y);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "nvecmanyvector")]
+fn nvecmanyvector_subvectors() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let mut vecs = [N_VNew_Serial(3, ctx), N_VNew_Serial(2, ctx)];
+        let v = N_VNew_ManyVector(2, vecs.as_mut_ptr(), ctx);
+        assert!(! v.is_null());
+        assert_eq!(N_VGetVectorID(v), N_Vector_ID_SUNDIALS_NVEC_MANYVECTOR);
+        assert_eq!(N_VGetNumSubvectors_ManyVector(v), 2);
+        assert_eq!(N_VGetLength(v), 5);
+        N_VConst(2., v);
+        assert_eq!(N_VGetSubvector_ManyVector(v, 1), vecs[1]);
+        let data = N_VGetSubvectorArrayPointer_ManyVector(v, 1);
+        assert_eq!(std::slice::from_raw_parts(data, 2), [2., 2.]);
+        assert_eq!(N_VDotProd(v, v), 20.);
+        N_VDestroy(v);
+        N_VDestroy(vecs[0]);
+        N_VDestroy(vecs[1]);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "nvecmpiplusx")]
+fn nvecmpiplusx_decay() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        let (comm, mpi_comm) = mpi_world();
+        assert!(SUNContext_Create(comm, &mut ctx) >= 0);
+        let local = N_VNew_Serial(N as sunindextype, ctx);
+        let y = N_VMake_MPIPlusX(mpi_comm, local, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_MPIPLUSX);
+        assert_eq!(N_VGetLocalVector_MPIPlusX(y), local);
+        decay(y, ctx);
+        N_VDestroy(y);
+        N_VDestroy(local);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+/// Initialize MPI and PETSc (once for all the tests, which run in
+/// parallel threads of a single process) and return the communicators
+/// of [`mpi_world`].
+#[cfg(feature = "petsc")]
+unsafe fn petsc_init() -> (SUNComm, MPI_Comm) {
+    let world = mpi_world();
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        assert_eq!(PetscInitializeNoArguments(), 0);
+    });
+    world
+}
+
+#[test]
+#[cfg(feature = "petsc")]
+fn nvecpetsc_ops() {
+    let mut ctx = ptr::null_mut();
+    let mut v = ptr::null_mut();
+    unsafe {
+        let (comm, mpi_comm) = petsc_init();
+        assert!(SUNContext_Create(comm, &mut ctx) >= 0);
+        assert_eq!(VecCreateMPI(mpi_comm, N as PetscInt, N as PetscInt,
+                                &mut v), 0);
+        let y = N_VMake_Petsc(v, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_PETSC);
+        assert_eq!(N_VGetVector_Petsc(y), v);
+        assert_eq!(N_VGetLength(y), N as sunindextype);
+        N_VConst(2., y);
+        assert_eq!(N_VL1Norm(y), 2. * N as realtype);
+        assert_eq!(N_VDotProd(y, y), 4. * N as realtype);
+        N_VDestroy(y);
+        VecDestroy(&mut v);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "petsc")]
+fn petscsnes_wrap() {
+    let mut ctx = ptr::null_mut();
+    let mut v = ptr::null_mut();
+    let mut snes = ptr::null_mut();
+    unsafe {
+        let (comm, mpi_comm) = petsc_init();
+        assert!(SUNContext_Create(comm, &mut ctx) >= 0);
+        assert_eq!(VecCreateMPI(mpi_comm, N as PetscInt, N as PetscInt,
+                                &mut v), 0);
+        let y = N_VMake_Petsc(v, ctx);
+        assert_eq!(SNESCreate(mpi_comm, &mut snes), 0);
+        let nls = SUNNonlinSol_PetscSNES(y, snes, ctx);
+        assert!(! nls.is_null());
+        assert_eq!(SUNNonlinSolGetType(nls),
+                   SUNNonlinearSolver_Type_SUNNONLINEARSOLVER_ROOTFIND);
+        let mut got = ptr::null_mut();
+        assert_eq!(SUNNonlinSol_PetscSNESGetSNES(nls, &mut got), 0);
+        assert_eq!(got, snes);
+        SUNNonlinSolFree(nls);
+        SNESDestroy(&mut snes);
+        N_VDestroy(y);
+        VecDestroy(&mut v);
+        SUNContext_Free(&mut ctx);
+    }
+}
+
+#[test]
+#[cfg(feature = "hypre")]
+fn nvecparhyp_ops() {
+    let mut ctx = ptr::null_mut();
+    let mut ij = ptr::null_mut();
+    let mut x = ptr::null_mut();
+    unsafe {
+        let (comm, mpi_comm) = mpi_world();
+        assert_eq!(HYPRE_Init(), 0);
+        assert!(SUNContext_Create(comm, &mut ctx) >= 0);
+        assert_eq!(HYPRE_IJVectorCreate(mpi_comm, 0, N as HYPRE_BigInt - 1,
+                                        &mut ij), 0);
+        assert_eq!(HYPRE_IJVectorSetObjectType(ij, HYPRE_PARCSR as c_int), 0);
+        assert_eq!(HYPRE_IJVectorInitialize(ij), 0);
+        assert_eq!(HYPRE_IJVectorAssemble(ij), 0);
+        assert_eq!(HYPRE_IJVectorGetObject(ij, &mut x), 0);
+        let y = N_VMake_ParHyp(x as HYPRE_ParVector, ctx);
+        assert!(! y.is_null());
+        assert_eq!(N_VGetVectorID(y), N_Vector_ID_SUNDIALS_NVEC_PARHYP);
+        assert_eq!(N_VGetVector_ParHyp(y), x as HYPRE_ParVector);
+        assert_eq!(N_VGetLength(y), N as sunindextype);
+        N_VConst(2., y);
+        assert_eq!(N_VL1Norm(y), 2. * N as realtype);
+        N_VDestroy(y);
+        HYPRE_IJVectorDestroy(ij);
+        SUNContext_Free(&mut ctx);
+    }
+}
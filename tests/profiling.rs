@@ -0,0 +1,26 @@
+#![cfg(all(sundials_has_profiling, not(feature = "dlopen")))]
+use std::ptr;
+use sundials_sys::*;
+
+/// Regions opened with `profiler_region` are timed by the profiler
+/// of the context.  (With Caliper, the times are only reported by it.)
+#[test]
+#[cfg(not(sundials_has_caliper))]
+fn profiler_region_timed() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let mut profiler = ptr::null_mut();
+        assert_eq!(SUNContext_GetProfiler(ctx, &mut profiler), 0);
+        assert!(!profiler.is_null());
+        {
+            let _region = profiler_region(ctx, c"user");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let mut time = 0.;
+        assert_eq!(SUNProfiler_GetElapsedTime(profiler, c"user".as_ptr(),
+                                              &mut time), 0);
+        assert!(time >= 0.005, "elapsed time {} too short", time);
+        SUNContext_Free(&mut ctx);
+    }
+}
@@ -0,0 +1,39 @@
+#![cfg(all(feature = "superlumt", not(feature = "dlopen"),
+           not(sundials_long_double)))]
+use std::{ptr, ffi::c_int};
+use sundials_sys::*;
+
+/// Solve a 2×2 diagonal sparse system with two threads.
+#[test]
+fn superlumt_solve() {
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        let a = SUNSparseMatrix(2, 2, 2, CSC_MAT as c_int, ctx);
+        let ptrs = SUNSparseMatrix_IndexPointers(a);
+        let vals = SUNSparseMatrix_IndexValues(a);
+        let data = SUNSparseMatrix_Data(a);
+        for i in 0..2 {
+            *ptrs.add(i) = i as sunindextype;
+            *vals.add(i) = i as sunindextype;
+            *data.add(i) = 2.;
+        }
+        *ptrs.add(2) = 2;
+        let x = N_VNew_Serial(2, ctx);
+        let b = N_VNew_Serial(2, ctx);
+        *N_VGetArrayPointer(b) = 2.;
+        *N_VGetArrayPointer(b).add(1) = 4.;
+        let ls = SUNLinSol_SuperLUMT(x, a, 2, ctx);
+        assert!(! ls.is_null());
+        assert_eq!(SUNLinSolInitialize(ls), 0);
+        assert_eq!(SUNLinSolSetup(ls, a), 0);
+        assert_eq!(SUNLinSolSolve(ls, a, x, b, 0.), 0);
+        let x_data = N_VGetArrayPointer(x);
+        assert_eq!([*x_data, *x_data.add(1)], [1., 2.]);
+        SUNLinSolFree(ls);
+        N_VDestroy(b);
+        N_VDestroy(x);
+        SUNMatDestroy(a);
+        SUNContext_Free(&mut ctx);
+    }
+}
@@ -0,0 +1,175 @@
+//! Exercise the API whose signatures changed between the major
+//! versions of SUNDIALS, to check that the `sundials_version_major`
+//! cfg emitted by the build script matches the bound library.
+
+#[allow(unused_imports)]
+use std::{ptr, ffi::{c_char, c_int, c_void}};
+use sundials_sys::*;
+
+#[test]
+fn version_major_cfg() {
+    #[cfg(sundials_version_major = "6")]
+    assert_eq!(SUNDIALS_VERSION_MAJOR, 6);
+    #[cfg(sundials_version_major = "7")]
+    assert_eq!(SUNDIALS_VERSION_MAJOR, 7);
+}
+
+#[test]
+fn version_minor_patch_cfgs() {
+    let (major, minor, patch) = SUNDIALS_LINKED_VERSION;
+    assert_eq!(major, SUNDIALS_VERSION_MAJOR as u32);
+    #[cfg(sundials_version_minor = "0")]
+    assert_eq!(minor, 0);
+    #[cfg(sundials_version_minor = "7")]
+    assert_eq!(minor, 7);
+    #[cfg(sundials_version_patch = "0")]
+    assert_eq!(patch, 0);
+    assert!(cfg!(sundials_version_at_least = "6.0"));
+    assert_eq!(cfg!(sundials_version_at_least = "6.2"),
+               (major, minor) >= (6, 2));
+    assert_eq!(cfg!(sundials_version_at_least = "7.1"),
+               (major, minor) >= (7, 1));
+    // SUNContext_PushErrHandler appeared in SUNDIALS 7.0.
+    #[cfg(sundials_version_at_least = "7.0")]
+    assert!(cfg!(sundials_has_fn_SUNContext_PushErrHandler));
+}
+
+#[test]
+fn has_fn_cfgs() {
+    // SUNContext_PushErrHandler appeared in SUNDIALS 7.0.
+    #[cfg(sundials_has_fn_SUNContext_PushErrHandler)]
+    assert!(SUNDIALS_VERSION_MAJOR >= 7);
+    #[cfg(sundials_version_major = "6")]
+    assert!(!cfg!(sundials_has_fn_SUNContext_PushErrHandler));
+}
+
+#[test]
+fn precision_cfg() {
+    use std::mem::size_of;
+    #[cfg(sundials_precision = "single")]
+    assert_eq!(size_of::<realtype>(), 4);
+    #[cfg(sundials_precision = "double")]
+    assert_eq!(size_of::<realtype>(), 8);
+    #[cfg(sundials_long_double)]
+    assert!(size_of::<realtype>() > 8);
+    #[cfg(feature = "single_precision")]
+    assert!(cfg!(sundials_precision = "single"));
+}
+
+#[test]
+fn index_size_cfg() {
+    use std::mem::size_of;
+    #[cfg(sundials_index_size = "32")]
+    assert_eq!(size_of::<sunindextype>(), 4);
+    #[cfg(sundials_index_size = "64")]
+    assert_eq!(size_of::<sunindextype>(), 8);
+    #[cfg(feature = "index_size_32")]
+    assert!(cfg!(sundials_index_size = "32"));
+}
+
+#[test]
+fn capability_cfgs() {
+    // Capabilities which imply others.
+    #[cfg(sundials_has_caliper)]
+    assert!(cfg!(sundials_has_profiling));
+    #[cfg(any(sundials_has_superludist, sundials_has_petsc,
+              sundials_has_hypre))]
+    assert!(cfg!(sundials_has_mpi));
+    // The vendored build enables exactly what the features request.
+    #[cfg(all(feature = "build_libraries", feature = "klu"))]
+    assert!(cfg!(sundials_has_klu));
+    #[cfg(all(feature = "build_libraries", feature = "lapack"))]
+    assert!(cfg!(sundials_has_lapack));
+}
+
+#[test]
+#[cfg(not(sundials_long_double))]
+fn renamed_types() {
+    // `realtype` must remain usable whatever the version.
+    fn same(x: realtype) -> sunrealtype { x }
+    assert_eq!(same(1.), 1.);
+}
+
+#[test]
+#[cfg(all(sundials_version_major = "7", not(sundials_long_double)))]
+#[allow(deprecated)]
+fn v6_names() {
+    fn same(b: booleantype) -> c_int { b }
+    assert_eq!(same(SUNTRUE), SUNTRUE);
+    assert_eq!(PREC_LEFT, SUN_PREC_LEFT as c_int);
+    assert_eq!(MODIFIED_GS, SUN_MODIFIED_GS as c_int);
+    assert!(UNIT_ROUNDOFF > 0. && UNIT_ROUNDOFF < 1e-5);
+}
+
+#[test]
+#[cfg(not(feature = "dlopen"))]
+fn context_create_comm() {
+    #[cfg(sundials_version_major = "6")]
+    let comm: *mut c_void = comm_no_mpi();
+    #[cfg(sundials_version_major = "7")]
+    let comm: SUNComm = comm_no_mpi();
+    #[cfg(all(sundials_version_major = "7", not(sundials_has_mpi)))]
+    assert_eq!(comm, SUN_COMM_NULL);
+    #[cfg(not(sundials_has_mpi))]
+    assert_eq!(comm_world(), comm);
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm, &mut ctx) >= 0);
+        assert!(! ctx.is_null());
+        assert!(SUNContext_Free(&mut ctx) >= 0);
+    }
+}
+
+/// Trigger an input error (negative relative tolerance) and check it
+/// is reported to the error handler registered with the API of the
+/// detected version.
+#[test]
+#[cfg(all(any(feature = "cvode", feature = "cvodes"),
+          not(feature = "dlopen")))]
+fn error_handler() {
+    extern "C" fn f(
+        _t: realtype, _y: N_Vector, _ydot: N_Vector, _user_data: *mut c_void,
+    ) -> c_int {
+        0
+    }
+
+    #[cfg(sundials_version_major = "6")]
+    unsafe extern "C" fn handler(
+        error_code: c_int, _module: *const c_char, _function: *const c_char,
+        _msg: *mut c_char, user_data: *mut c_void,
+    ) {
+        *(user_data as *mut c_int) = error_code;
+    }
+
+    #[cfg(sundials_version_major = "7")]
+    unsafe extern "C" fn handler(
+        _line: c_int, _func: *const c_char, _file: *const c_char,
+        _msg: *const c_char, err_code: SUNErrCode, user_data: *mut c_void,
+        _sunctx: SUNContext,
+    ) {
+        *(user_data as *mut c_int) = err_code;
+    }
+
+    let mut err: c_int = 0;
+    let err_ptr = &mut err as *mut c_int as *mut c_void;
+    let mut ctx = ptr::null_mut();
+    unsafe {
+        assert!(SUNContext_Create(comm_no_mpi(), &mut ctx) >= 0);
+        #[cfg(sundials_version_major = "7")]
+        assert_eq!(SUNContext_PushErrHandler(ctx, Some(handler), err_ptr),
+                   SUN_SUCCESS as SUNErrCode);
+        let y = N_VNew_Serial(1, ctx);
+        let mut cvode_mem = CVodeCreate(CV_BDF, ctx);
+        assert_eq!(CVodeInit(cvode_mem, Some(f), 0., y), CV_SUCCESS);
+        #[cfg(sundials_version_major = "6")]
+        assert_eq!(CVodeSetErrHandlerFn(cvode_mem, Some(handler), err_ptr),
+                   CV_SUCCESS);
+
+        assert_eq!(CVodeSStolerances(cvode_mem, -1., 1e-8), CV_ILL_INPUT);
+        assert_eq!(err, CV_ILL_INPUT);
+
+        N_VDestroy(y);
+        CVodeFree(&mut cvode_mem);
+        SUNContext_Free(&mut ctx);
+    }
+}